@@ -0,0 +1,103 @@
+use skynet_api::{
+    HyUuid, Result,
+    entity::{groups, user_group_links, users},
+    sea_orm::{self, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect},
+};
+use skynet_api_task::{
+    entity::scripts,
+    viewer::acls::{AclEntry, AclViewer},
+};
+
+/// Groups of user `uid`, empty for anonymous requests.
+async fn groups<C>(db: &C, uid: Option<HyUuid>) -> Result<Vec<HyUuid>>
+where
+    C: ConnectionTrait,
+{
+    let Some(uid) = uid else {
+        return Ok(Vec::new());
+    };
+    user_group_links::Entity::find()
+        .select_only()
+        .column(user_group_links::Column::Gid)
+        .filter(user_group_links::Column::Uid.eq(uid))
+        .into_tuple()
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Whether user `uid` is granted `perm` on script `sid`, on top of the global permission.
+pub async fn check<C>(db: &C, sid: &HyUuid, uid: Option<HyUuid>, perm: i32) -> Result<bool>
+where
+    C: ConnectionTrait,
+{
+    AclViewer::check(db, sid, uid, &groups(db, uid).await?, perm).await
+}
+
+/// Scripts in `sid` on which user `uid` is not granted `perm`.
+pub async fn denied<C>(
+    db: &C,
+    sid: &[HyUuid],
+    uid: Option<HyUuid>,
+    perm: i32,
+) -> Result<Vec<HyUuid>>
+where
+    C: ConnectionTrait,
+{
+    let gid = groups(db, uid).await?;
+    let mut ret = Vec::new();
+    for x in sid {
+        if !AclViewer::check(db, x, uid, &gid, perm).await? {
+            ret.push(*x);
+        }
+    }
+    Ok(ret)
+}
+
+/// Condition of scripts on which user `uid` is granted any bit of `perm`.
+pub async fn cond<C>(db: &C, uid: Option<HyUuid>, perm: i32) -> Result<sea_orm::Condition>
+where
+    C: ConnectionTrait,
+{
+    let restricted = AclViewer::find_restricted(db).await?;
+    let allowed = match uid {
+        Some(uid) => AclViewer::find_allowed(db, &uid, &groups(db, Some(uid)).await?, perm).await?,
+        None => Vec::new(),
+    };
+    Ok(sea_orm::Condition::any()
+        .add(scripts::Column::Id.is_not_in(restricted))
+        .add(scripts::Column::Id.is_in(allowed)))
+}
+
+/// Principals in `acl` that are not an existing user or group as declared.
+pub async fn unknown<C>(db: &C, acl: &[AclEntry]) -> Result<Vec<HyUuid>>
+where
+    C: ConnectionTrait,
+{
+    let (gid, uid): (Vec<_>, Vec<_>) = acl.iter().partition(|x| x.group);
+    let found: Vec<HyUuid> = users::Entity::find()
+        .select_only()
+        .column(users::Column::Id)
+        .filter(users::Column::Id.is_in(uid.iter().map(|x| x.principal)))
+        .into_tuple()
+        .all(db)
+        .await?;
+    let mut ret: Vec<_> = uid
+        .iter()
+        .map(|x| x.principal)
+        .filter(|x| !found.contains(x))
+        .collect();
+    let found: Vec<HyUuid> = groups::Entity::find()
+        .select_only()
+        .column(groups::Column::Id)
+        .filter(groups::Column::Id.is_in(gid.iter().map(|x| x.principal)))
+        .into_tuple()
+        .all(db)
+        .await?;
+    ret.extend(
+        gid.iter()
+            .map(|x| x.principal)
+            .filter(|x| !found.contains(x)),
+    );
+    Ok(ret)
+}
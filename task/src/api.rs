@@ -1,32 +1,69 @@
+use std::collections::{BTreeMap, HashSet};
+
 use actix_cloud::{
-    actix_web::web::{Data, Path},
+    actix_web::{
+        HttpRequest, HttpResponse,
+        http::header::{self, ContentDisposition, DispositionParam, DispositionType},
+        web::{Data, Path, ReqData},
+    },
+    chrono::Utc,
     response::{JsonResponse, RspResult},
-    tracing::info,
+    tracing::{error, info},
 };
 use actix_web_validator::{Json, QsQuery};
 use serde::{Deserialize, Serialize};
 use serde_inline_default::serde_inline_default;
 use skynet_api::{
-    HyUuid,
+    HyUuid, Result,
     ffi_rpc::registry::Registry,
     finish,
-    request::{Condition, IDsReq, IntoExpr, PageData, PaginationParam, TimeParam},
-    sea_orm::{ColumnTrait, IntoSimpleExpr, TransactionTrait},
+    permission::{PERM_WRITE, PermChecker},
+    request::{Condition, IDsReq, IntoExpr, PageData, PaginationParam, Request, TimeParam},
+    sea_orm::{self, ColumnTrait, ConnectionTrait, IntoSimpleExpr, TransactionTrait},
 };
 use skynet_api_task::{
-    Service,
-    entity::{scripts, tasks},
-    viewer::{scripts::ScriptViewer, tasks::TaskViewer},
+    Service, TaskEventKind, Value,
+    entity::{
+        notifications,
+        scripts::{self, Concurrency},
+        secrets,
+        tasks::{self, Approval},
+    },
+    viewer::{
+        acls::{ACL_EDIT, ACL_RUN, ACL_VIEW, AclEntry, AclViewer},
+        artifacts::ArtifactViewer,
+        notifications::{NotificationRule, NotificationViewer},
+        scripts::{ScriptInfo, ScriptPatch, ScriptViewer},
+        secrets::SecretViewer,
+        states::StateViewer,
+        tags::TagViewer,
+        tasks::{TaskRun, TaskViewer},
+    },
 };
 use skynet_macro::common_req;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
-use crate::{PLUGIN_INSTANCE, TaskResponse};
+use crate::{
+    PLUGIN_INSTANCE, Plugin, TaskResponse, acl, artifact,
+    bundle::{self, Bundle, Conflict},
+    events,
+    logging::{LogLevel, filter_output},
+    notify,
+    retention::Retention,
+    service::Approve,
+    stats::Stats,
+    stub::StubRegistry,
+    sync::SyncSetting,
+    visibility::Visibility,
+};
 
 #[common_req(tasks::Column)]
 #[derive(Debug, Validate, Deserialize)]
 pub struct GetTasksReq {
     pub text: Option<String>,
+    /// Only list tasks awaiting approval.
+    #[serde(default)]
+    pub pending: bool,
 
     #[serde(flatten)]
     #[validate(nested)]
@@ -36,7 +73,11 @@ pub struct GetTasksReq {
     pub time: TimeParam,
 }
 
-pub async fn get_tasks(param: QsQuery<GetTasksReq>) -> RspResult<JsonResponse> {
+pub async fn get_tasks(
+    param: QsQuery<GetTasksReq>,
+    req: ReqData<Request>,
+) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
     let mut cond = param.common_cond();
     if let Some(text) = &param.text {
         cond = cond.add(
@@ -47,7 +88,13 @@ pub async fn get_tasks(param: QsQuery<GetTasksReq>) -> RspResult<JsonResponse> {
                 .add(text.like_expr(tasks::Column::Output)),
         );
     }
-    let data = TaskViewer::find(PLUGIN_INSTANCE.db.get().unwrap(), cond).await?;
+    if param.pending {
+        cond = cond.add(tasks::Column::Approval.eq(Approval::Pending));
+    }
+    if let Some(x) = Visibility::cond(db, &req).await? {
+        cond = cond.add(x);
+    }
+    let data = TaskViewer::find(db, cond).await?;
     finish!(JsonResponse::new(TaskResponse::Success).json(PageData::new(data)));
 }
 
@@ -57,20 +104,23 @@ pub struct GetOutputReq {
     #[validate(range(min = 0))]
     #[serde_inline_default(0)]
     pub pos: usize,
+    pub level: Option<LogLevel>,
 }
 
 pub async fn get_output(
     tid: Path<HyUuid>,
     param: QsQuery<GetOutputReq>,
+    req: ReqData<Request>,
 ) -> RspResult<JsonResponse> {
     #[derive(Serialize)]
     struct Rsp {
         output: String,
         pos: usize,
     }
-    let t = match TaskViewer::find_by_id(PLUGIN_INSTANCE.db.get().unwrap(), &tid).await? {
-        Some(t) => t.output,
-        None => finish!(JsonResponse::not_found()),
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let t = match TaskViewer::find_by_id(db, &tid).await? {
+        Some(t) if Visibility::visible(db, &req, &t.id).await? => t.output,
+        _ => finish!(JsonResponse::not_found()),
     }
     .unwrap_or_default();
 
@@ -79,20 +129,445 @@ pub async fn get_output(
     } else {
         ""
     };
+    finish!(
+        JsonResponse::new(TaskResponse::Success).json(Rsp {
+            output: param
+                .level
+                .map_or_else(|| t.to_owned(), |x| filter_output(t, x)),
+            pos: param.pos + t.len()
+        })
+    );
+}
+
+pub async fn get_artifacts(tid: Path<HyUuid>, req: ReqData<Request>) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    if TaskViewer::find_by_id(db, &tid).await?.is_none()
+        || !Visibility::visible(db, &req, &tid).await?
+    {
+        finish!(JsonResponse::not_found());
+    }
+    let data = ArtifactViewer::find_by_tid(db, &tid).await?;
+    finish!(JsonResponse::new(TaskResponse::Success).json(data));
+}
+
+/// Download artifact `aid` of task `tid` as a file.
+pub async fn get_artifact(
+    path: Path<(HyUuid, HyUuid)>,
+    req: ReqData<Request>,
+    http: HttpRequest,
+) -> RspResult<HttpResponse> {
+    let (tid, aid) = path.into_inner();
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let m = match ArtifactViewer::find_by_id(db, &aid).await? {
+        Some(m) if m.tid == tid && Visibility::visible(db, &req, &tid).await? => m,
+        _ => return Ok(HttpResponse::NotFound().finish()),
+    };
+    Ok(artifact::open(&m)
+        .await?
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(m.name.clone())],
+        })
+        .into_response(&http))
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    /// Awaiting approval, not started yet.
+    Pending,
+    Running,
+    Success,
+    Failed,
+    /// Rejected by the approver, not started.
+    Rejected,
+}
+
+impl TaskStatus {
+    pub fn of(t: &tasks::Model) -> Self {
+        match (t.approval, t.result) {
+            (Some(Approval::Rejected), _) => Self::Rejected,
+            (Some(Approval::Pending), None) => Self::Pending,
+            (_, None) => Self::Running,
+            (_, Some(0)) => Self::Success,
+            (_, Some(_)) => Self::Failed,
+        }
+    }
+}
+
+pub async fn get_task(tid: Path<HyUuid>, req: ReqData<Request>) -> RspResult<JsonResponse> {
+    #[derive(Serialize)]
+    struct Script {
+        id: HyUuid,
+        name: String,
+    }
+    #[derive(Serialize)]
+    struct Rsp {
+        #[serde(flatten)]
+        task: tasks::Model,
+        status: TaskStatus,
+        duration: Option<i64>,
+        script: Option<Script>,
+        params: Option<BTreeMap<String, Value>>,
+    }
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let task = match TaskViewer::find_by_id(db, &tid).await? {
+        Some(t) if Visibility::visible(db, &req, &t.id).await? => t,
+        _ => finish!(JsonResponse::not_found()),
+    };
+    let script = match task.sid {
+        Some(sid) => ScriptViewer::find_by_id(db, &sid).await?.map(|x| Script {
+            id: x.id,
+            name: x.name,
+        }),
+        None => None,
+    };
+    let duration = task.started_at.map(|x| {
+        task.finished_at
+            .unwrap_or_else(|| Utc::now().timestamp_millis())
+            - x
+    });
     finish!(JsonResponse::new(TaskResponse::Success).json(Rsp {
-        output: t.to_owned(),
-        pos: param.pos + t.len()
+        status: TaskStatus::of(&task),
+        duration,
+        script,
+        params: TaskViewer::params(&task)?,
+        task,
     }));
 }
 
-pub async fn delete_completed() -> RspResult<JsonResponse> {
-    let cnt = TaskViewer::delete_completed(PLUGIN_INSTANCE.db.get().unwrap()).await?;
-    info!(success = true, "Delete all tasks");
+#[derive(Debug, Validate, Deserialize)]
+pub struct DeleteTasksReq {
+    pub id: Option<Vec<HyUuid>>,
+    pub name: Option<String>,
+    /// Running and pending tasks cannot be deleted by filter.
+    #[validate(custom(function = "validate_completed"))]
+    pub status: Option<TaskStatus>,
+    /// Only delete tasks finished more than `days` days ago.
+    #[validate(range(min = 1))]
+    pub days: Option<u32>,
+}
+
+fn validate_completed(status: &TaskStatus) -> Result<(), ValidationError> {
+    if matches!(status, TaskStatus::Running | TaskStatus::Pending) {
+        return Err(ValidationError::new("running"));
+    }
+    Ok(())
+}
+
+pub async fn delete_completed(
+    param: Json<DeleteTasksReq>,
+    req: ReqData<Request>,
+) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let mut cond = sea_orm::Condition::all();
+    if let Some(x) = Visibility::cond(db, &req).await? {
+        cond = cond.add(x);
+    }
+    if let Some(id) = &param.id {
+        cond = cond.add(tasks::Column::Id.is_in(id.iter().copied()));
+    }
+    if let Some(name) = &param.name {
+        cond = cond.add(name.like_expr(tasks::Column::Name));
+    }
+    match param.status {
+        Some(TaskStatus::Running | TaskStatus::Pending) | None => {}
+        Some(TaskStatus::Success) => cond = cond.add(tasks::Column::Result.eq(0)),
+        Some(TaskStatus::Failed) => {
+            cond = cond.add(tasks::Column::Result.ne(0)).add(
+                Condition::any()
+                    .add(tasks::Column::Approval.is_null())
+                    .add(tasks::Column::Approval.ne(Approval::Rejected)),
+            )
+        }
+        Some(TaskStatus::Rejected) => {
+            cond = cond.add(tasks::Column::Approval.eq(Approval::Rejected))
+        }
+    }
+    if let Some(days) = param.days {
+        cond = cond.add(
+            tasks::Column::UpdatedAt
+                .lt(Utc::now().timestamp_millis() - i64::from(days) * 24 * 3600 * 1000),
+        );
+    }
+    let cnt = TaskViewer::delete_completed_by(db, cond).await?;
+    artifact::clean().await?;
+    info!(success = true, filter = ?*param, "Delete tasks");
     finish!(JsonResponse::new(TaskResponse::Success).json(cnt));
 }
 
-pub async fn stop(tid: Path<HyUuid>, reg: Data<Registry>) -> RspResult<JsonResponse> {
-    if !PLUGIN_INSTANCE.stop(&reg, *tid).await {
+#[derive(Debug, Validate, Deserialize)]
+pub struct DeleteTaskReq {
+    /// Stop running tasks before deleting them.
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Delete tasks `id`, running tasks are stopped first when `force` is set.
+/// Return the running tasks instead if any of them is running and `force` is not set.
+/// Pending tasks are not started and deleted directly.
+async fn delete_tasks(
+    reg: &Registry,
+    id: &[HyUuid],
+    force: bool,
+) -> Result<Result<u64, Vec<HyUuid>>> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let running = TaskViewer::find_running(db, id).await?;
+    if !running.is_empty() {
+        if !force {
+            return Ok(Err(running));
+        }
+        for x in running {
+            PLUGIN_INSTANCE.stop(reg, x).await;
+        }
+    }
+    let rows = TaskViewer::delete(db, id).await?;
+    artifact::clean().await?;
+    Ok(Ok(rows))
+}
+
+pub async fn delete_task_batch(
+    param: Json<IDsReq>,
+    query: QsQuery<DeleteTaskReq>,
+    req: ReqData<Request>,
+    reg: Data<Registry>,
+) -> RspResult<JsonResponse> {
+    // Invisible tasks are treated as not found.
+    let id = Visibility::filter(PLUGIN_INSTANCE.db.get().unwrap(), &req, &param.id).await?;
+    match delete_tasks(&reg, &id, query.force).await? {
+        Ok(rows) => {
+            if rows != 0 {
+                info!(
+                    success = true,
+                    id = ?param.id,
+                    force = query.force,
+                    "Delete tasks",
+                );
+            }
+            finish!(JsonResponse::new(TaskResponse::Success).json(rows));
+        }
+        Err(running) => finish!(JsonResponse::new(TaskResponse::TaskRunning).json(running)),
+    }
+}
+
+pub async fn delete_task(
+    tid: Path<HyUuid>,
+    query: QsQuery<DeleteTaskReq>,
+    req: ReqData<Request>,
+    reg: Data<Registry>,
+) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    if TaskViewer::find_by_id(db, &tid).await?.is_none()
+        || !Visibility::visible(db, &req, &tid).await?
+    {
+        finish!(JsonResponse::not_found());
+    }
+    match delete_tasks(&reg, &[*tid], query.force).await? {
+        Ok(rows) => {
+            info!(
+                success = true,
+                id = %tid,
+                force = query.force,
+                "Delete task",
+            );
+            finish!(JsonResponse::new(TaskResponse::Success).json(rows));
+        }
+        Err(running) => finish!(JsonResponse::new(TaskResponse::TaskRunning).json(running)),
+    }
+}
+
+pub async fn rerun(
+    tid: Path<HyUuid>,
+    req: ReqData<Request>,
+    reg: Data<Registry>,
+) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let t = match TaskViewer::find_by_id(db, &tid).await? {
+        Some(t) if Visibility::visible(db, &req, &t.id).await? => t,
+        _ => finish!(JsonResponse::not_found()),
+    };
+    match TaskStatus::of(&t) {
+        TaskStatus::Running => {
+            finish!(JsonResponse::new(TaskResponse::TaskRunning).json(vec![t.id]))
+        }
+        TaskStatus::Pending => finish!(JsonResponse::new(TaskResponse::TaskPending)),
+        _ => {}
+    }
+    let params = TaskViewer::params(&t)?;
+    let (Some(code), Some(params)) = (t.code, params) else {
+        finish!(JsonResponse::new(TaskResponse::TaskNotScript));
+    };
+    if let Some(sid) = &t.sid {
+        if ScriptViewer::find_by_id(db, sid)
+            .await?
+            .is_some_and(|x| !x.runnable())
+        {
+            finish!(JsonResponse::new(TaskResponse::ScriptDisabled));
+        }
+        if !acl::check(db, sid, req.uid, ACL_RUN).await? {
+            finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+        }
+    }
+    let run = TaskRun {
+        sid: t.sid,
+        code,
+        params,
+        parent: Some(t.id),
+        uid: req.uid,
+        dry_run: t.dry_run,
+        plugin: None,
+        key: None,
+    };
+    let ret = PLUGIN_INSTANCE
+        .run_code(&reg, t.name, t.detail, run, None)
+        .await?;
+    info!(
+        success = true,
+        id = %tid,
+        "Rerun task",
+    );
+    finish!(JsonResponse::new(TaskResponse::Success).json(ret));
+}
+
+pub async fn get_retention() -> RspResult<JsonResponse> {
+    let ret = Retention::get(PLUGIN_INSTANCE.db.get().unwrap()).await?;
+    finish!(JsonResponse::new(TaskResponse::Success).json(ret));
+}
+
+pub async fn put_retention(param: Json<Retention>) -> RspResult<JsonResponse> {
+    param.set(PLUGIN_INSTANCE.db.get().unwrap()).await?;
+    info!(success = true, retention = ?*param, "Put task retention");
+    finish!(JsonResponse::new(TaskResponse::Success));
+}
+
+pub async fn get_sync() -> RspResult<JsonResponse> {
+    let ret = SyncSetting::get(PLUGIN_INSTANCE.db.get().unwrap()).await?;
+    finish!(JsonResponse::new(TaskResponse::Success).json(ret));
+}
+
+pub async fn put_sync(param: Json<SyncSetting>) -> RspResult<JsonResponse> {
+    param.set(PLUGIN_INSTANCE.db.get().unwrap()).await?;
+    info!(success = true, sync = ?*param, "Put script sync");
+    finish!(JsonResponse::new(TaskResponse::Success));
+}
+
+pub async fn get_visibility() -> RspResult<JsonResponse> {
+    let ret = Visibility::get(PLUGIN_INSTANCE.db.get().unwrap()).await?;
+    finish!(JsonResponse::new(TaskResponse::Success).json(ret));
+}
+
+pub async fn put_visibility(param: Json<Visibility>) -> RspResult<JsonResponse> {
+    param.set(PLUGIN_INSTANCE.db.get().unwrap()).await?;
+    info!(success = true, visibility = ?*param, "Put task visibility");
+    finish!(JsonResponse::new(TaskResponse::Success));
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct DecideTaskReq {
+    #[validate(length(max = 256))]
+    pub reason: Option<String>,
+}
+
+/// Check whether task `t` can be decided by `req`.
+/// The requester cannot decide unless the script no longer requires approval.
+async fn check_pending<C>(db: &C, t: &tasks::Model, req: &Request) -> Result<Option<TaskResponse>>
+where
+    C: ConnectionTrait,
+{
+    if t.approval != Some(Approval::Pending) || t.result.is_some() {
+        return Ok(Some(TaskResponse::TaskNotPending));
+    }
+    if t.uid.is_some() && t.uid == req.uid {
+        let required = match &t.sid {
+            Some(sid) => ScriptViewer::find_by_id(db, sid)
+                .await?
+                .is_none_or(|x| x.approval),
+            None => true,
+        };
+        if required {
+            return Ok(Some(TaskResponse::TaskApprover));
+        }
+    }
+    Ok(None)
+}
+
+pub async fn approve_task(
+    tid: Path<HyUuid>,
+    param: Json<DecideTaskReq>,
+    req: ReqData<Request>,
+    reg: Data<Registry>,
+) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let t = match TaskViewer::find_by_id(db, &tid).await? {
+        Some(t) if Visibility::visible(db, &req, &t.id).await? => t,
+        _ => finish!(JsonResponse::not_found()),
+    };
+    if let Some(x) = check_pending(db, &t, &req).await? {
+        finish!(JsonResponse::new(x));
+    }
+    // The script may be changed while the task is pending.
+    if let Some(sid) = &t.sid {
+        if ScriptViewer::find_by_id(db, sid)
+            .await?
+            .is_some_and(|x| !x.runnable())
+        {
+            finish!(JsonResponse::new(TaskResponse::ScriptDisabled));
+        }
+        if !acl::check(db, sid, t.uid, ACL_RUN).await? {
+            finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+        }
+    }
+    match PLUGIN_INSTANCE
+        .approve(&reg, t, req.uid, &param.reason)
+        .await?
+    {
+        Approve::Started => {}
+        Approve::Running(x) => {
+            finish!(JsonResponse::new(TaskResponse::TaskRunning).json(vec![x]))
+        }
+        Approve::NotPending => finish!(JsonResponse::new(TaskResponse::TaskNotPending)),
+    }
+    info!(success = true, id = %tid, "Approve task");
+    finish!(JsonResponse::new(TaskResponse::Success));
+}
+
+pub async fn reject_task(
+    tid: Path<HyUuid>,
+    param: Json<DecideTaskReq>,
+    req: ReqData<Request>,
+    reg: Data<Registry>,
+) -> RspResult<JsonResponse> {
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    let t = match TaskViewer::find_by_id(&tx, &tid).await? {
+        Some(t) if Visibility::visible(&tx, &req, &t.id).await? => t,
+        _ => finish!(JsonResponse::not_found()),
+    };
+    if let Some(x) = check_pending(&tx, &t, &req).await? {
+        finish!(JsonResponse::new(x));
+    }
+    if !TaskViewer::decide(&tx, &tid, Approval::Rejected, req.uid, &param.reason).await? {
+        finish!(JsonResponse::new(TaskResponse::TaskNotPending));
+    }
+    TaskViewer::finish_out(&tx, &tid, 9, "Task rejected by the approver").await?;
+    tx.commit().await?;
+    if let Err(e) = events::emit(&reg, TaskEventKind::Finished, &tid).await {
+        error!(success = false, id = %tid, error = %e, "Emit task event");
+    }
+    if let Err(e) = notify::finish(&reg, &tid).await {
+        error!(success = false, id = %tid, error = %e, "Notify task");
+    }
+    info!(success = true, id = %tid, reason = ?param.reason, "Reject task");
+    finish!(JsonResponse::new(TaskResponse::Success));
+}
+
+pub async fn stop(
+    tid: Path<HyUuid>,
+    req: ReqData<Request>,
+    reg: Data<Registry>,
+) -> RspResult<JsonResponse> {
+    if !Visibility::visible(PLUGIN_INSTANCE.db.get().unwrap(), &req, &tid).await?
+        || !PLUGIN_INSTANCE.stop(&reg, *tid).await
+    {
         finish!(JsonResponse::not_found());
     }
     info!(success = true, id = %tid, "Stop task");
@@ -103,6 +578,13 @@ pub async fn stop(tid: Path<HyUuid>, reg: Data<Registry>) -> RspResult<JsonRespo
 #[derive(Debug, Validate, Deserialize)]
 pub struct GetScriptsReq {
     pub text: Option<String>,
+    /// List archived scripts instead of active ones.
+    #[serde(default)]
+    pub archived: bool,
+    pub enabled: Option<bool>,
+    /// Scripts in `folder` and its subfolders.
+    pub folder: Option<String>,
+    pub tag: Option<String>,
 
     #[serde(flatten)]
     #[validate(nested)]
@@ -112,29 +594,77 @@ pub struct GetScriptsReq {
     pub time: TimeParam,
 }
 
-pub async fn get_scripts(param: QsQuery<GetScriptsReq>) -> RspResult<JsonResponse> {
+pub async fn get_scripts(
+    param: QsQuery<GetScriptsReq>,
+    req: ReqData<Request>,
+) -> RspResult<JsonResponse> {
     #[derive(Serialize)]
     struct Rsp {
         id: HyUuid,
         name: String,
+        library: bool,
+        concurrency: Concurrency,
+        readonly: bool,
+        enabled: bool,
+        approval: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        archived_at: Option<i64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        folder: Option<String>,
+        tags: Vec<String>,
         created_at: i64,
         updated_at: i64,
     }
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
     let mut cond = param.common_cond();
     if let Some(text) = &param.text {
         cond = cond.add(
             Condition::any()
                 .add(text.like_expr(scripts::Column::Id))
-                .add(text.like_expr(scripts::Column::Name)),
+                .add(text.like_expr(scripts::Column::Name))
+                .add(text.like_expr(scripts::Column::Description)),
         );
     }
-    let data = ScriptViewer::find(PLUGIN_INSTANCE.db.get().unwrap(), cond).await?;
+    if let Some(folder) = param.folder.as_deref().and_then(normalize_folder) {
+        cond = cond.add(
+            Condition::any()
+                .add(scripts::Column::Folder.eq(folder.as_str()))
+                .add(scripts::Column::Folder.starts_with(format!("{folder}/"))),
+        );
+    }
+    cond = cond.add(if param.archived {
+        scripts::Column::ArchivedAt.is_not_null()
+    } else {
+        scripts::Column::ArchivedAt.is_null()
+    });
+    if let Some(enabled) = param.enabled {
+        cond = cond.add(scripts::Column::Enabled.eq(enabled));
+    }
+    if let Some(tag) = &param.tag {
+        cond = cond.add(scripts::Column::Id.is_in(TagViewer::find_sids(db, tag).await?));
+    }
+    // Scripts are listed if any right is granted, runners need to find them.
+    cond = cond.add(acl::cond(db, req.uid, ACL_RUN | ACL_VIEW | ACL_EDIT).await?);
+    let data = ScriptViewer::find(db, cond).await?;
+    let id: Vec<_> = data.0.iter().map(|x| x.id).collect();
+    let mut tags = TagViewer::get_map(db, &id).await?;
     let data = (
         data.0
             .into_iter()
             .map(|x| Rsp {
                 id: x.id,
                 name: x.name,
+                library: x.library,
+                concurrency: x.concurrency,
+                readonly: x.source.is_some(),
+                enabled: x.enabled,
+                approval: x.approval,
+                archived_at: x.archived_at,
+                description: x.description,
+                folder: x.folder,
+                tags: tags.remove(&x.id).unwrap_or_default(),
                 created_at: x.created_at,
                 updated_at: x.updated_at,
             })
@@ -144,45 +674,178 @@ pub async fn get_scripts(param: QsQuery<GetScriptsReq>) -> RspResult<JsonRespons
     finish!(JsonResponse::new(TaskResponse::Success).json(PageData::new(data)));
 }
 
-pub async fn get_script(sid: Path<HyUuid>) -> RspResult<JsonResponse> {
-    if let Some(script) = ScriptViewer::find_by_id(PLUGIN_INSTANCE.db.get().unwrap(), &sid).await? {
-        finish!(JsonResponse::new(TaskResponse::Success).json(script));
+pub async fn get_script(sid: Path<HyUuid>, req: ReqData<Request>) -> RspResult<JsonResponse> {
+    #[derive(Serialize)]
+    struct Rsp {
+        #[serde(flatten)]
+        script: scripts::Model,
+        tags: Vec<String>,
+    }
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    if let Some(script) = ScriptViewer::find_by_id(db, &sid).await? {
+        if !acl::check(db, &sid, req.uid, ACL_VIEW).await? {
+            finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+        }
+        let tags = TagViewer::get(db, &sid).await?;
+        finish!(JsonResponse::new(TaskResponse::Success).json(Rsp { script, tags }));
     } else {
         finish!(JsonResponse::not_found());
     }
 }
 
+/// Trim `/` around folder path `x`, `None` for the root folder.
+pub fn normalize_folder(x: &str) -> Option<String> {
+    let x = x.trim().trim_matches('/');
+    (!x.is_empty()).then(|| x.to_owned())
+}
+
+pub fn validate_tags(tags: &[String]) -> Result<(), ValidationError> {
+    if tags.iter().any(|x| x.is_empty() || x.chars().count() > 64) {
+        Err(ValidationError::new("length"))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct ScriptDiagnostic {
+    message: String,
+    line: Option<usize>,
+    column: Option<usize>,
+}
+
+/// Compile `code` and return the diagnostic when it contains syntax errors.
+fn check_script(code: &str) -> Option<ScriptDiagnostic> {
+    Plugin::compile_script(code)
+        .err()
+        .map(|e| ScriptDiagnostic {
+            message: e.err_type().to_string(),
+            line: e.position().line(),
+            column: e.position().position(),
+        })
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct ValidateScriptReq {
+    pub code: String,
+}
+
+pub async fn validate_script(param: Json<ValidateScriptReq>) -> RspResult<JsonResponse> {
+    if let Some(x) = check_script(&param.code) {
+        finish!(JsonResponse::new(TaskResponse::ScriptSyntax).json(x));
+    }
+    finish!(JsonResponse::new(TaskResponse::Success));
+}
+
 #[derive(Debug, Validate, Deserialize)]
 pub struct AddScriptReq {
-    #[validate(length(min = 1, max = 32))]
+    #[validate(length(min = 1, max = 128))]
     pub name: String,
     pub code: String,
+    #[serde(default)]
+    pub library: bool,
+    #[serde(default)]
+    pub concurrency: Concurrency,
+    #[validate(length(max = 1024))]
+    pub description: Option<String>,
+    #[validate(length(max = 256))]
+    pub folder: Option<String>,
+    #[serde(default)]
+    #[validate(length(max = 32), custom(function = "validate_tags"))]
+    pub tags: Vec<String>,
 }
 
 pub async fn add_script(param: Json<AddScriptReq>) -> RspResult<JsonResponse> {
-    let script =
-        ScriptViewer::create(PLUGIN_INSTANCE.db.get().unwrap(), &param.name, &param.code).await?;
+    if let Some(x) = check_script(&param.code) {
+        finish!(JsonResponse::new(TaskResponse::ScriptSyntax).json(x));
+    }
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    if param.library
+        && ScriptViewer::find_library(&tx, &param.name)
+            .await?
+            .is_some()
+    {
+        finish!(JsonResponse::new(TaskResponse::ScriptExist));
+    }
+    let script = ScriptViewer::create(
+        &tx,
+        &ScriptInfo {
+            name: param.name.clone(),
+            code: param.code.clone(),
+            library: param.library,
+            concurrency: param.concurrency,
+            description: param.description.clone().filter(|x| !x.is_empty()),
+            folder: param.folder.as_deref().and_then(normalize_folder),
+            source: None,
+        },
+    )
+    .await?;
+    TagViewer::set(&tx, &script.id, &param.tags).await?;
+    tx.commit().await?;
     info!(success = true, name = param.name, "Add script");
     finish!(JsonResponse::new(TaskResponse::Success).json(script.id));
 }
 
 #[derive(Debug, Validate, Deserialize)]
 pub struct PutScriptReq {
-    #[validate(length(min = 1, max = 32))]
+    #[validate(length(min = 1, max = 128))]
     pub name: Option<String>,
     pub code: Option<String>,
+    pub library: Option<bool>,
+    pub concurrency: Option<Concurrency>,
+    /// Empty string clears the description.
+    #[validate(length(max = 1024))]
+    pub description: Option<String>,
+    /// Empty string moves the script to the root folder.
+    #[validate(length(max = 256))]
+    pub folder: Option<String>,
+    #[validate(length(max = 32), custom(function = "validate_tags"))]
+    pub tags: Option<Vec<String>>,
 }
 
-pub async fn put_script(sid: Path<HyUuid>, param: Json<PutScriptReq>) -> RspResult<JsonResponse> {
+pub async fn put_script(
+    sid: Path<HyUuid>,
+    param: Json<PutScriptReq>,
+    req: ReqData<Request>,
+) -> RspResult<JsonResponse> {
+    if let Some(x) = param.code.as_deref().and_then(check_script) {
+        finish!(JsonResponse::new(TaskResponse::ScriptSyntax).json(x));
+    }
     let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
     if let Some(script) = ScriptViewer::find_by_id(&tx, &sid).await? {
+        if script.source.is_some() {
+            finish!(JsonResponse::new(TaskResponse::ScriptReadonly));
+        }
+        if !acl::check(&tx, &sid, req.uid, ACL_EDIT).await? {
+            finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+        }
+        if param.library.unwrap_or(script.library)
+            && ScriptViewer::find_library(&tx, param.name.as_deref().unwrap_or(&script.name))
+                .await?
+                .is_some_and(|x| x.id != script.id)
+        {
+            finish!(JsonResponse::new(TaskResponse::ScriptExist));
+        }
         ScriptViewer::update(
             &tx,
             &script.id,
-            param.name.as_deref(),
-            param.code.as_deref(),
+            &ScriptPatch {
+                name: param.name.clone(),
+                code: param.code.clone(),
+                library: param.library,
+                concurrency: param.concurrency,
+                description: param
+                    .description
+                    .clone()
+                    .map(|x| Some(x).filter(|x| !x.is_empty())),
+                folder: param.folder.as_deref().map(normalize_folder),
+                ..Default::default()
+            },
         )
         .await?;
+        if let Some(tags) = &param.tags {
+            TagViewer::set(&tx, &script.id, tags).await?;
+        }
     } else {
         finish!(JsonResponse::not_found());
     }
@@ -195,41 +858,340 @@ pub async fn put_script(sid: Path<HyUuid>, param: Json<PutScriptReq>) -> RspResu
     finish!(JsonResponse::new(TaskResponse::Success));
 }
 
-pub async fn delete_script_batch(param: Json<IDsReq>) -> RspResult<JsonResponse> {
-    let rows = ScriptViewer::delete(PLUGIN_INSTANCE.db.get().unwrap(), &param.id).await?;
+pub async fn delete_script_batch(
+    param: Json<IDsReq>,
+    req: ReqData<Request>,
+) -> RspResult<JsonResponse> {
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    let synced = ScriptViewer::find_synced_in(&tx, &param.id).await?;
+    if !synced.is_empty() {
+        finish!(JsonResponse::new(TaskResponse::ScriptReadonly).json(synced));
+    }
+    let denied = acl::denied(&tx, &param.id, req.uid, ACL_EDIT).await?;
+    if !denied.is_empty() {
+        finish!(JsonResponse::new(TaskResponse::ScriptDenied).json(denied));
+    }
+    let tasks = TaskViewer::count_by_sids(&tx, &param.id).await?;
+    let rows = ScriptViewer::delete(&tx, &param.id).await?;
+    tx.commit().await?;
     if rows != 0 {
         info!(
             success = true,
             sid = ?param.id,
+            tasks,
             "Delete scripts",
         );
     }
-    finish!(JsonResponse::new(TaskResponse::Success).json(rows));
+    finish!(JsonResponse::new(TaskResponse::Success).json(DeleteScriptRsp { rows, tasks }));
+}
+
+/// Tasks of deleted scripts are kept without the script.
+#[derive(Serialize)]
+struct DeleteScriptRsp {
+    rows: u64,
+    /// Number of tasks that referenced the deleted scripts.
+    tasks: u64,
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct PutScriptStatusReq {
+    pub enabled: Option<bool>,
+    pub archived: Option<bool>,
+    /// Require approval before running.
+    pub approval: Option<bool>,
+}
+
+/// Whether `req` can change the approval requirement of scripts.
+fn can_require_approval(req: &Request) -> bool {
+    [
+        PLUGIN_INSTANCE.approve_id.get().unwrap(),
+        PLUGIN_INSTANCE.admin_id.get().unwrap(),
+    ]
+    .into_iter()
+    .any(|x| PermChecker::new_entry(*x, PERM_WRITE).check(&req.perm))
+}
+
+/// Enable, archive or require approval of script `sid`, synced scripts are allowed.
+/// Only approvers and admins can change the approval requirement.
+pub async fn put_script_status(
+    sid: Path<HyUuid>,
+    param: Json<PutScriptStatusReq>,
+    req: ReqData<Request>,
+) -> RspResult<JsonResponse> {
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    let Some(script) = ScriptViewer::find_by_id(&tx, &sid).await? else {
+        finish!(JsonResponse::not_found());
+    };
+    if !acl::check(&tx, &sid, req.uid, ACL_EDIT).await? {
+        finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+    }
+    if param.approval.is_some_and(|x| x != script.approval) && !can_require_approval(&req) {
+        finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+    }
+    let archived_at = param.archived.map(|x| {
+        x.then(|| {
+            script
+                .archived_at
+                .unwrap_or_else(|| Utc::now().timestamp_millis())
+        })
+    });
+    ScriptViewer::update(
+        &tx,
+        &script.id,
+        &ScriptPatch {
+            enabled: param.enabled,
+            archived_at,
+            approval: param.approval,
+            ..Default::default()
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    info!(
+        success = true,
+        sid = %sid,
+        enabled = ?param.enabled,
+        archived = ?param.archived,
+        approval = ?param.approval,
+        "Put script status",
+    );
+    finish!(JsonResponse::new(TaskResponse::Success));
 }
 
-pub async fn delete_script(sid: Path<HyUuid>) -> RspResult<JsonResponse> {
+pub async fn get_script_acl(sid: Path<HyUuid>, req: ReqData<Request>) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    if ScriptViewer::find_by_id(db, &sid).await?.is_none() {
+        finish!(JsonResponse::not_found());
+    }
+    if !acl::check(db, &sid, req.uid, ACL_EDIT).await? {
+        finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+    }
+    let acl = AclViewer::get(db, &sid).await?;
+    finish!(JsonResponse::new(TaskResponse::Success).json(acl));
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct PutScriptAclReq {
+    /// Empty to remove the restriction.
+    #[validate(custom(function = "validate_acl"))]
+    pub acl: Vec<AclEntry>,
+}
+
+fn validate_acl(acl: &[AclEntry]) -> Result<(), ValidationError> {
+    let mask = ACL_RUN | ACL_VIEW | ACL_EDIT;
+    if acl.iter().any(|x| x.perm & !mask != 0) {
+        return Err(ValidationError::new("perm"));
+    }
+    let principal: HashSet<_> = acl.iter().map(|x| (x.principal, x.group)).collect();
+    if principal.len() != acl.len() {
+        return Err(ValidationError::new("principal"));
+    }
+    Ok(())
+}
+
+/// Replace ACL of script `sid`, the requester must keep the edit right.
+pub async fn put_script_acl(
+    sid: Path<HyUuid>,
+    param: Json<PutScriptAclReq>,
+    req: ReqData<Request>,
+) -> RspResult<JsonResponse> {
     let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
     if ScriptViewer::find_by_id(&tx, &sid).await?.is_none() {
         finish!(JsonResponse::not_found());
     }
+    if !acl::check(&tx, &sid, req.uid, ACL_EDIT).await? {
+        finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+    }
+    let unknown = acl::unknown(&tx, &param.acl).await?;
+    if !unknown.is_empty() {
+        finish!(JsonResponse::new(TaskResponse::AclPrincipal).json(unknown));
+    }
+    AclViewer::set(&tx, &sid, &param.acl).await?;
+    // Avoid locking the requester out.
+    if !acl::check(&tx, &sid, req.uid, ACL_EDIT).await? {
+        finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+    }
+    tx.commit().await?;
+    info!(
+        success = true,
+        sid = %sid,
+        entries = param.acl.len(),
+        "Put script acl",
+    );
+    finish!(JsonResponse::new(TaskResponse::Success));
+}
+
+pub async fn delete_script(sid: Path<HyUuid>, req: ReqData<Request>) -> RspResult<JsonResponse> {
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    match ScriptViewer::find_by_id(&tx, &sid).await? {
+        Some(x) if x.source.is_some() => finish!(JsonResponse::new(TaskResponse::ScriptReadonly)),
+        Some(_) => {}
+        None => finish!(JsonResponse::not_found()),
+    }
+    if !acl::check(&tx, &sid, req.uid, ACL_EDIT).await? {
+        finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+    }
+    let tasks = TaskViewer::count_by_sids(&tx, &[*sid]).await?;
     let rows = ScriptViewer::delete(&tx, &[*sid]).await?;
     tx.commit().await?;
     info!(
         success = true,
         sid = %sid,
+        tasks,
         "Delete script",
     );
+    finish!(JsonResponse::new(TaskResponse::Success).json(DeleteScriptRsp { rows, tasks }));
+}
+
+pub async fn export_scripts(param: Json<IDsReq>, req: ReqData<Request>) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let denied = acl::denied(db, &param.id, req.uid, ACL_VIEW).await?;
+    if !denied.is_empty() {
+        finish!(JsonResponse::new(TaskResponse::ScriptDenied).json(denied));
+    }
+    let bundle = bundle::export(db, &param.id).await?;
+    finish!(JsonResponse::new(TaskResponse::Success).json(bundle));
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct ImportScriptsReq {
+    #[validate(nested)]
+    pub bundle: Bundle,
+    #[serde(default)]
+    pub conflict: Conflict,
+}
+
+pub async fn import_scripts(
+    param: Json<ImportScriptsReq>,
+    req: ReqData<Request>,
+) -> RspResult<JsonResponse> {
+    #[derive(Serialize)]
+    struct Rsp {
+        name: String,
+        #[serde(flatten)]
+        diagnostic: ScriptDiagnostic,
+    }
+    if param.bundle.version != bundle::VERSION {
+        finish!(JsonResponse::new(TaskResponse::BundleVersion));
+    }
+    for x in &param.bundle.scripts {
+        if let Some(diagnostic) = check_script(&x.code) {
+            finish!(JsonResponse::new(TaskResponse::ScriptSyntax).json(Rsp {
+                name: x.name.clone(),
+                diagnostic,
+            }));
+        }
+    }
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    let ret = bundle::import(
+        &tx,
+        &param.bundle,
+        param.conflict,
+        req.uid,
+        can_require_approval(&req),
+    )
+    .await?;
+    tx.commit().await?;
+    info!(
+        success = true,
+        created = ret.created.len(),
+        updated = ret.updated.len(),
+        skipped = ret.skipped.len(),
+        "Import scripts",
+    );
+    finish!(JsonResponse::new(TaskResponse::Success).json(ret));
+}
+
+pub async fn get_state(sid: Path<HyUuid>, req: ReqData<Request>) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    if ScriptViewer::find_by_id(db, &sid).await?.is_none() {
+        finish!(JsonResponse::not_found());
+    }
+    if !acl::check(db, &sid, req.uid, ACL_VIEW).await? {
+        finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+    }
+    let state = StateViewer::get_all(db, &sid).await?;
+    finish!(JsonResponse::new(TaskResponse::Success).json(state));
+}
+
+pub async fn reset_state(sid: Path<HyUuid>, req: ReqData<Request>) -> RspResult<JsonResponse> {
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    if ScriptViewer::find_by_id(&tx, &sid).await?.is_none() {
+        finish!(JsonResponse::not_found());
+    }
+    if !acl::check(&tx, &sid, req.uid, ACL_EDIT).await? {
+        finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+    }
+    let rows = StateViewer::reset(&tx, &sid).await?;
+    tx.commit().await?;
+    info!(
+        success = true,
+        sid = %sid,
+        "Reset script state",
+    );
     finish!(JsonResponse::new(TaskResponse::Success).json(rows));
 }
 
-pub async fn run_script(sid: Path<HyUuid>, reg: Data<Registry>) -> RspResult<JsonResponse> {
-    if let Some(s) = ScriptViewer::find_by_id(PLUGIN_INSTANCE.db.get().unwrap(), &sid).await? {
+#[derive(Debug, Deserialize)]
+pub struct MockResponse {
+    pub plugin: String,
+    pub name: String,
+    pub response: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct RunScriptReq {
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub mock: Vec<MockResponse>,
+    #[serde(default)]
+    pub param: BTreeMap<String, Value>,
+}
+
+pub async fn run_script(
+    sid: Path<HyUuid>,
+    param: Json<RunScriptReq>,
+    req: ReqData<Request>,
+    reg: Data<Registry>,
+) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    if let Some(s) = ScriptViewer::find_by_id(db, &sid).await? {
+        if s.library {
+            finish!(JsonResponse::new(TaskResponse::ScriptLibrary));
+        }
+        if !acl::check(db, &sid, req.uid, ACL_RUN).await? {
+            finish!(JsonResponse::new(TaskResponse::ScriptDenied));
+        }
+        if !s.runnable() {
+            finish!(JsonResponse::new(TaskResponse::ScriptDisabled));
+        }
+        let (name, stub) = if param.dry_run {
+            let mut stub = StubRegistry::default();
+            for x in &param.mock {
+                stub.insert(&x.plugin, &x.name, x.response.clone());
+            }
+            (format!("Dry run `{}`", s.name), Some(stub))
+        } else {
+            (format!("Manual run `{}`", s.name), None)
+        };
+        let run = TaskRun {
+            sid: Some(s.id),
+            code: s.code,
+            params: param.param.clone(),
+            parent: None,
+            uid: req.uid,
+            dry_run: param.dry_run,
+            plugin: None,
+            key: None,
+        };
         let ret = PLUGIN_INSTANCE
-            .create_code(&reg, format!("Manual run `{}`", s.name), None, s.code)
+            .run_code(&reg, name, None, run, stub)
             .await?;
         info!(
             success = true,
             sid = %sid,
+            dry_run = param.dry_run,
             "Run script",
         );
         finish!(JsonResponse::new(TaskResponse::Success).json(ret));
@@ -237,3 +1199,360 @@ pub async fn run_script(sid: Path<HyUuid>, reg: Data<Registry>) -> RspResult<Jso
         finish!(JsonResponse::not_found());
     }
 }
+
+#[common_req(secrets::Column)]
+#[derive(Debug, Validate, Deserialize)]
+pub struct GetSecretsReq {
+    pub text: Option<String>,
+
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub page: PaginationParam,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub time: TimeParam,
+}
+
+pub async fn get_secrets(param: QsQuery<GetSecretsReq>) -> RspResult<JsonResponse> {
+    let mut cond = param.common_cond();
+    if let Some(text) = &param.text {
+        cond = cond.add(
+            Condition::any()
+                .add(text.like_expr(secrets::Column::Id))
+                .add(text.like_expr(secrets::Column::Name)),
+        );
+    }
+    let data = SecretViewer::find(PLUGIN_INSTANCE.db.get().unwrap(), cond).await?;
+    finish!(JsonResponse::new(TaskResponse::Success).json(PageData::new(data)));
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct AddSecretReq {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    pub value: String,
+}
+
+pub async fn add_secret(param: Json<AddSecretReq>) -> RspResult<JsonResponse> {
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    if SecretViewer::find_by_name(&tx, &param.name)
+        .await?
+        .is_some()
+    {
+        finish!(JsonResponse::new(TaskResponse::SecretExist));
+    }
+    let value = PLUGIN_INSTANCE
+        .secret
+        .get()
+        .unwrap()
+        .encrypt(&param.value)?;
+    let secret = SecretViewer::create(&tx, &param.name, &value).await?;
+    tx.commit().await?;
+    info!(success = true, name = param.name, "Add secret");
+    finish!(JsonResponse::new(TaskResponse::Success).json(secret.id));
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct PutSecretReq {
+    pub value: String,
+}
+
+pub async fn put_secret(id: Path<HyUuid>, param: Json<PutSecretReq>) -> RspResult<JsonResponse> {
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    if SecretViewer::find_by_id(&tx, &id).await?.is_none() {
+        finish!(JsonResponse::not_found());
+    }
+    let value = PLUGIN_INSTANCE
+        .secret
+        .get()
+        .unwrap()
+        .encrypt(&param.value)?;
+    SecretViewer::update(&tx, &id, &value).await?;
+    tx.commit().await?;
+    info!(
+        success = true,
+        id = %id,
+        "Put secret",
+    );
+    finish!(JsonResponse::new(TaskResponse::Success));
+}
+
+pub async fn delete_secret(id: Path<HyUuid>) -> RspResult<JsonResponse> {
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    if SecretViewer::find_by_id(&tx, &id).await?.is_none() {
+        finish!(JsonResponse::not_found());
+    }
+    let rows = SecretViewer::delete(&tx, &[*id]).await?;
+    tx.commit().await?;
+    info!(
+        success = true,
+        id = %id,
+        "Delete secret",
+    );
+    finish!(JsonResponse::new(TaskResponse::Success).json(rows));
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct GetStatsReq {
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub time: TimeParam,
+}
+
+pub async fn get_stats(
+    param: QsQuery<GetStatsReq>,
+    req: ReqData<Request>,
+) -> RspResult<JsonResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let stats = Stats::compute(
+        db,
+        param.time.created_start,
+        param.time.created_end,
+        Visibility::cond(db, &req).await?,
+    )
+    .await?;
+    finish!(JsonResponse::new(TaskResponse::Success).json(stats));
+}
+
+/// Metrics of tasks matching `visible`, all tasks if `None`.
+async fn metrics(
+    param: &GetStatsReq,
+    visible: Option<sea_orm::Condition>,
+) -> RspResult<HttpResponse> {
+    let stats = Stats::compute(
+        PLUGIN_INSTANCE.db.get().unwrap(),
+        param.time.created_start,
+        param.time.created_end,
+        visible,
+    )
+    .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(stats.prometheus()))
+}
+
+pub async fn get_metrics(
+    param: QsQuery<GetStatsReq>,
+    req: ReqData<Request>,
+) -> RspResult<HttpResponse> {
+    let visible = Visibility::cond(PLUGIN_INSTANCE.db.get().unwrap(), &req).await?;
+    metrics(&param, visible).await
+}
+
+/// Metrics for scrapers, authorized by the `Authorization: Bearer` token instead of session.
+/// The token is set by admins and covers all tasks.
+pub async fn get_metrics_scrape(
+    http: HttpRequest,
+    param: QsQuery<GetStatsReq>,
+) -> RspResult<HttpResponse> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let auth = http
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|x| x.to_str().ok());
+    if !Stats::check_token(db, auth).await? {
+        return Ok(HttpResponse::Unauthorized().finish());
+    }
+    metrics(&param, None).await
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct PutMetricsTokenReq {
+    #[validate(length(min = 16, max = 256))]
+    pub token: Option<String>,
+}
+
+pub async fn put_metrics_token(param: Json<PutMetricsTokenReq>) -> RspResult<JsonResponse> {
+    Stats::set_token(PLUGIN_INSTANCE.db.get().unwrap(), param.token.as_deref()).await?;
+    info!(
+        success = true,
+        enabled = param.token.is_some(),
+        "Put metrics token"
+    );
+    finish!(JsonResponse::new(TaskResponse::Success));
+}
+
+#[common_req(notifications::Column)]
+#[derive(Debug, Validate, Deserialize)]
+pub struct GetNotificationsReq {
+    pub text: Option<String>,
+
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub page: PaginationParam,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub time: TimeParam,
+}
+
+pub async fn get_notifications(param: QsQuery<GetNotificationsReq>) -> RspResult<JsonResponse> {
+    let mut cond = param.common_cond();
+    if let Some(text) = &param.text {
+        cond = cond.add(
+            Condition::any()
+                .add(text.like_expr(notifications::Column::Id))
+                .add(text.like_expr(notifications::Column::Name)),
+        );
+    }
+    let data = NotificationViewer::find(PLUGIN_INSTANCE.db.get().unwrap(), cond).await?;
+    finish!(JsonResponse::new(TaskResponse::Success).json(PageData::new(data)));
+}
+
+#[derive(Debug, Validate, Deserialize)]
+pub struct NotificationReq {
+    #[validate(length(min = 1, max = 32))]
+    pub name: String,
+    #[validate(url, length(max = 1024))]
+    pub url: Option<String>,
+    #[validate(length(min = 1, max = 256))]
+    pub callback: Option<String>,
+    pub sid: Option<HyUuid>,
+    #[serde(default)]
+    pub on_success: bool,
+    #[serde(default)]
+    pub on_failure: bool,
+}
+
+impl NotificationReq {
+    fn rule(&self) -> NotificationRule {
+        NotificationRule {
+            name: self.name.clone(),
+            url: self.url.clone(),
+            callback: self.callback.clone(),
+            sid: self.sid,
+            on_success: self.on_success,
+            on_failure: self.on_failure,
+        }
+    }
+}
+
+pub async fn add_notification(param: Json<NotificationReq>) -> RspResult<JsonResponse> {
+    if param.url.is_none() && param.callback.is_none() {
+        finish!(JsonResponse::new(TaskResponse::NotificationTarget));
+    }
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    if let Some(sid) = &param.sid
+        && ScriptViewer::find_by_id(&tx, sid).await?.is_none()
+    {
+        finish!(JsonResponse::not_found());
+    }
+    let m = NotificationViewer::create(&tx, &param.rule()).await?;
+    tx.commit().await?;
+    info!(success = true, name = param.name, "Add notification");
+    finish!(JsonResponse::new(TaskResponse::Success).json(m.id));
+}
+
+pub async fn put_notification(
+    id: Path<HyUuid>,
+    param: Json<NotificationReq>,
+) -> RspResult<JsonResponse> {
+    if param.url.is_none() && param.callback.is_none() {
+        finish!(JsonResponse::new(TaskResponse::NotificationTarget));
+    }
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    if NotificationViewer::find_by_id(&tx, &id).await?.is_none() {
+        finish!(JsonResponse::not_found());
+    }
+    if let Some(sid) = &param.sid
+        && ScriptViewer::find_by_id(&tx, sid).await?.is_none()
+    {
+        finish!(JsonResponse::not_found());
+    }
+    NotificationViewer::update(&tx, &id, &param.rule()).await?;
+    tx.commit().await?;
+    info!(
+        success = true,
+        id = %id,
+        name = param.name,
+        "Put notification",
+    );
+    finish!(JsonResponse::new(TaskResponse::Success));
+}
+
+pub async fn delete_notification(id: Path<HyUuid>) -> RspResult<JsonResponse> {
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    if NotificationViewer::find_by_id(&tx, &id).await?.is_none() {
+        finish!(JsonResponse::not_found());
+    }
+    let rows = NotificationViewer::delete(&tx, &[*id]).await?;
+    tx.commit().await?;
+    info!(
+        success = true,
+        id = %id,
+        "Delete notification",
+    );
+    finish!(JsonResponse::new(TaskResponse::Success).json(rows));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(principal: HyUuid, perm: i32) -> AclEntry {
+        AclEntry {
+            principal,
+            group: false,
+            perm,
+        }
+    }
+
+    #[test]
+    fn validate_acl_entries() {
+        let (a, b) = (HyUuid::new(), HyUuid::new());
+        assert!(validate_acl(&[]).is_ok());
+        assert!(validate_acl(&[entry(a, ACL_RUN | ACL_VIEW | ACL_EDIT), entry(b, 0)]).is_ok());
+        assert_eq!(
+            validate_acl(&[entry(a, ACL_EDIT << 1)]).unwrap_err().code,
+            "perm"
+        );
+        assert_eq!(
+            validate_acl(&[entry(a, ACL_RUN), entry(a, ACL_VIEW)])
+                .unwrap_err()
+                .code,
+            "principal"
+        );
+        let group = AclEntry {
+            group: true,
+            ..entry(a, ACL_VIEW)
+        };
+        assert!(validate_acl(&[entry(a, ACL_RUN), group]).is_ok());
+    }
+
+    #[test]
+    fn task_status() {
+        let t = |approval, result| tasks::Model {
+            approval,
+            result,
+            ..Default::default()
+        };
+        assert!(matches!(
+            TaskStatus::of(&t(None, None)),
+            TaskStatus::Running
+        ));
+        assert!(matches!(
+            TaskStatus::of(&t(None, Some(0))),
+            TaskStatus::Success
+        ));
+        assert!(matches!(
+            TaskStatus::of(&t(None, Some(1))),
+            TaskStatus::Failed
+        ));
+        assert!(matches!(
+            TaskStatus::of(&t(Some(Approval::Pending), None)),
+            TaskStatus::Pending
+        ));
+        assert!(matches!(
+            TaskStatus::of(&t(Some(Approval::Approved), None)),
+            TaskStatus::Running
+        ));
+        assert!(matches!(
+            TaskStatus::of(&t(Some(Approval::Rejected), Some(9))),
+            TaskStatus::Rejected
+        ));
+        // Stopped while pending.
+        assert!(matches!(
+            TaskStatus::of(&t(Some(Approval::Pending), Some(9))),
+            TaskStatus::Failed
+        ));
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use actix_cloud::tokio::fs;
+use actix_files::NamedFile;
+use skynet_api::{
+    HyUuid, Result, bail,
+    sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, TransactionTrait},
+};
+use skynet_api_task::{
+    entity::{artifacts, tasks},
+    viewer::{artifacts::ArtifactViewer, tasks::TaskViewer},
+};
+
+use crate::PLUGIN_INSTANCE;
+
+/// Maximum size of an artifact.
+pub const MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// Maximum length of an artifact name in bytes.
+pub const MAX_NAME: usize = 256;
+
+fn root() -> PathBuf {
+    PLUGIN_INSTANCE
+        .runtime_path
+        .get()
+        .unwrap()
+        .join("artifacts")
+}
+
+fn path(m: &artifacts::Model) -> PathBuf {
+    root().join(m.tid.to_string()).join(m.id.to_string())
+}
+
+/// Check artifact `name` and `content` against [`MAX_NAME`] and [`MAX_SIZE`].
+pub fn check(name: &str, content: &[u8]) -> Result<()> {
+    if name.is_empty() || name.len() > MAX_NAME {
+        bail!("Artifact name should be 1 to {MAX_NAME} bytes");
+    }
+    if content.len() > MAX_SIZE {
+        bail!("Artifact `{name}` is larger than {MAX_SIZE} bytes");
+    }
+    Ok(())
+}
+
+/// Save `content` as artifact `name` of task `tid`, `None` if the task does not exist.
+/// Artifacts failing [`check`] are rejected.
+pub async fn write(tid: &HyUuid, name: &str, content: &[u8]) -> Result<Option<artifacts::Model>> {
+    check(name, content)?;
+    let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+    if TaskViewer::find_by_id(&tx, tid).await?.is_none() {
+        return Ok(None);
+    }
+    let m = ArtifactViewer::create(&tx, tid, name, content.len().try_into()?).await?;
+    let path = path(&m);
+    if let Some(x) = path.parent() {
+        fs::create_dir_all(x).await?;
+    }
+    fs::write(path, content).await?;
+    tx.commit().await?;
+    Ok(Some(m))
+}
+
+/// Open artifact `m` to be streamed as a file.
+pub async fn open(m: &artifacts::Model) -> Result<NamedFile> {
+    Ok(NamedFile::open_async(path(m)).await?)
+}
+
+/// Remove stored artifacts of deleted tasks.
+pub async fn clean() -> Result<()> {
+    let root = root();
+    if !fs::try_exists(&root).await? {
+        return Ok(());
+    }
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let mut dir = fs::read_dir(root).await?;
+    while let Some(x) = dir.next_entry().await? {
+        let tid = x.file_name().to_string_lossy().to_string();
+        if tasks::Entity::find()
+            .filter(tasks::Column::Id.eq(tid))
+            .count(db)
+            .await?
+            == 0
+        {
+            fs::remove_dir_all(x.path()).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_limits() {
+        assert!(check("a", b"").is_ok());
+        assert!(check("", b"").is_err());
+        assert!(check(&"a".repeat(MAX_NAME), b"").is_ok());
+        assert!(check(&"a".repeat(MAX_NAME + 1), b"").is_err());
+        assert!(check("a", &vec![0; MAX_SIZE + 1]).is_err());
+    }
+}
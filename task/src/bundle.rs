@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+use skynet_api::{
+    HyUuid, Result,
+    sea_orm::{ConnectionTrait, DatabaseTransaction},
+};
+use skynet_api_task::{
+    entity::scripts::{self, Concurrency},
+    viewer::{
+        acls::ACL_EDIT,
+        scripts::{ScriptInfo, ScriptPatch, ScriptViewer},
+        tags::TagViewer,
+    },
+};
+use validator::Validate;
+
+use crate::{
+    acl,
+    api::{normalize_folder, validate_tags},
+};
+
+/// Current version of the bundle format.
+pub const VERSION: u32 = 1;
+
+/// Script in a bundle.
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct BundleScript {
+    #[validate(length(min = 1, max = 128))]
+    pub name: String,
+    pub code: String,
+    #[serde(default)]
+    pub library: bool,
+    #[serde(default)]
+    pub concurrency: Concurrency,
+    #[validate(length(max = 1024))]
+    pub description: Option<String>,
+    #[validate(length(max = 256))]
+    pub folder: Option<String>,
+    #[serde(default)]
+    #[validate(length(max = 32), custom(function = "validate_tags"))]
+    pub tags: Vec<String>,
+    /// Require approval before running.
+    #[serde(default)]
+    pub approval: bool,
+}
+
+impl BundleScript {
+    fn new(value: scripts::Model, tags: Vec<String>) -> Self {
+        Self {
+            name: value.name,
+            code: value.code,
+            library: value.library,
+            concurrency: value.concurrency,
+            description: value.description,
+            folder: value.folder,
+            tags,
+            approval: value.approval,
+        }
+    }
+}
+
+/// Versioned bundle used to move scripts between instances.
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct Bundle {
+    pub version: u32,
+    #[validate(nested)]
+    pub scripts: Vec<BundleScript>,
+}
+
+/// How to import a script whose name already exists.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Conflict {
+    /// Keep the existing script.
+    #[default]
+    Skip,
+    /// Replace the existing script.
+    Overwrite,
+    /// Import with a new name.
+    Rename,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportResult {
+    pub created: Vec<HyUuid>,
+    pub updated: Vec<HyUuid>,
+    pub skipped: Vec<String>,
+}
+
+/// Export scripts `id` into a bundle.
+pub async fn export<C>(db: &C, id: &[HyUuid]) -> Result<Bundle>
+where
+    C: ConnectionTrait,
+{
+    let mut tags = TagViewer::get_map(db, id).await?;
+    Ok(Bundle {
+        version: VERSION,
+        scripts: ScriptViewer::find_by_ids(db, id)
+            .await?
+            .into_iter()
+            .map(|x| {
+                let tags = tags.remove(&x.id).unwrap_or_default();
+                BundleScript::new(x, tags)
+            })
+            .collect(),
+    })
+}
+
+/// Import scripts of `bundle` for user `uid`, resolving name conflicts with `conflict`.
+/// Synced scripts and scripts `uid` cannot edit are never overwritten.
+///
+/// Created scripts keep the approval requirement of the bundle, while overwritten scripts
+/// only change it if `approver` is set.
+pub async fn import(
+    tx: &DatabaseTransaction,
+    bundle: &Bundle,
+    conflict: Conflict,
+    uid: Option<HyUuid>,
+    approver: bool,
+) -> Result<ImportResult> {
+    let mut ret = ImportResult::default();
+    for x in &bundle.scripts {
+        let mut name = x.name.clone();
+        if let Some(old) = ScriptViewer::find_by_name(tx, &name).await? {
+            match conflict {
+                Conflict::Overwrite
+                    if old.source.is_some() || !acl::check(tx, &old.id, uid, ACL_EDIT).await? =>
+                {
+                    ret.skipped.push(name);
+                    continue;
+                }
+                // Library names are unique.
+                Conflict::Overwrite
+                    if x.library
+                        && ScriptViewer::find_library(tx, &name)
+                            .await?
+                            .is_some_and(|x| x.id != old.id) =>
+                {
+                    ret.skipped.push(name);
+                    continue;
+                }
+                Conflict::Skip => {
+                    ret.skipped.push(name);
+                    continue;
+                }
+                Conflict::Overwrite => {
+                    ScriptViewer::update(
+                        tx,
+                        &old.id,
+                        &ScriptPatch {
+                            code: Some(x.code.clone()),
+                            library: Some(x.library),
+                            concurrency: Some(x.concurrency),
+                            description: Some(x.description.clone()),
+                            folder: Some(x.folder.as_deref().and_then(normalize_folder)),
+                            approval: approver.then_some(x.approval),
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                    TagViewer::set(tx, &old.id, &x.tags).await?;
+                    ret.updated.push(old.id);
+                    continue;
+                }
+                Conflict::Rename => name = rename(tx, &name).await?,
+            }
+        }
+        let m = ScriptViewer::create(
+            tx,
+            &ScriptInfo {
+                name,
+                code: x.code.clone(),
+                library: x.library,
+                concurrency: x.concurrency,
+                description: x.description.clone(),
+                folder: x.folder.as_deref().and_then(normalize_folder),
+                source: None,
+            },
+        )
+        .await?;
+        if x.approval {
+            ScriptViewer::update(
+                tx,
+                &m.id,
+                &ScriptPatch {
+                    approval: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+        TagViewer::set(tx, &m.id, &x.tags).await?;
+        ret.created.push(m.id);
+    }
+    Ok(ret)
+}
+
+/// Find an unused name for script `name` by appending a number.
+async fn rename(tx: &DatabaseTransaction, name: &str) -> Result<String> {
+    let mut n = 1;
+    loop {
+        let suffix = format!("-{n}");
+        let mut ret: String = name
+            .chars()
+            .take(128usize.saturating_sub(suffix.len()))
+            .collect();
+        ret.push_str(&suffix);
+        if ScriptViewer::find_by_name(tx, &ret).await?.is_none() {
+            return Ok(ret);
+        }
+        n += 1;
+    }
+}
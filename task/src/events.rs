@@ -0,0 +1,62 @@
+use actix_cloud::{
+    tokio::{runtime, sync::mpsc, task::JoinHandle},
+    tracing::error,
+};
+use skynet_api::{HyUuid, Result, ffi_rpc::registry::Registry};
+use skynet_api_task::{TaskEvent, TaskEventKind, TaskEvents, viewer::tasks::TaskViewer};
+
+use crate::PLUGIN_INSTANCE;
+
+/// Start the job delivering events in order, so that slow subscribers do not block tasks.
+pub fn start() -> JoinHandle<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<(Registry, TaskEvent)>();
+    *PLUGIN_INSTANCE.sender.write().unwrap() = Some(tx);
+    runtime::Handle::current().spawn(async move {
+        while let Some((reg, event)) = rx.recv().await {
+            deliver(&reg, &event).await;
+        }
+    })
+}
+
+async fn deliver(reg: &Registry, event: &TaskEvent) {
+    let cb: Vec<String> = PLUGIN_INSTANCE
+        .events
+        .iter()
+        .filter(|x| x.value().matches(event))
+        .map(|x| x.key().to_owned())
+        .collect();
+    for x in cb {
+        match reg.get(&x) {
+            Some(r) => {
+                let r: TaskEvents = r.into();
+                r.on_event(reg, event).await;
+            }
+            None => error!(success = false, callback = x, "Subscriber not exist"),
+        }
+    }
+}
+
+/// Queue `kind` event of task `id` for matching subscribers.
+pub async fn emit(reg: &Registry, kind: TaskEventKind, id: &HyUuid) -> Result<()> {
+    if PLUGIN_INSTANCE.events.is_empty() {
+        return Ok(());
+    }
+    let Some(task) = TaskViewer::find_by_id(PLUGIN_INSTANCE.db.get().unwrap(), id).await? else {
+        return Ok(());
+    };
+    if task.dry_run {
+        return Ok(());
+    }
+    let event = TaskEvent {
+        kind,
+        id: task.id,
+        sid: task.sid,
+        name: task.name,
+        percent: task.percent,
+        result: task.result,
+    };
+    if let Some(x) = PLUGIN_INSTANCE.sender.read().unwrap().as_ref() {
+        x.send((reg.clone(), event))?;
+    }
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use std::{
     path::PathBuf,
-    sync::{Arc, OnceLock},
+    sync::{Arc, OnceLock, RwLock},
 };
 
 use actix_cloud::{
@@ -9,13 +9,19 @@ use actix_cloud::{
     memorydb,
     router::CSRFType,
     state::{GlobalState, ServerHandle},
-    tokio,
+    tokio::{
+        self,
+        sync::{Mutex, mpsc::UnboundedSender},
+        task::JoinHandle,
+    },
 };
 use dashmap::DashMap;
 use migration::migrator::Migrator;
 use sea_orm_migration::MigratorTrait;
+use secret::SecretCipher;
 use skynet_api::{
-    HyUuid, MenuItem, Skynet,
+    HyUuid, MenuItem, Result, Skynet,
+    entity::{permission_links, permissions},
     ffi_rpc::{
         self,
         abi_stable::prefix_type::PrefixTypeTrait,
@@ -26,20 +32,37 @@ use skynet_api::{
         registry::Registry,
         rmp_serde,
     },
-    permission::{PERM_READ, PERM_WRITE, PermChecker},
+    permission::{PERM_READ, PERM_WRITE, PermChecker, PermEntry},
     plugin::{PluginStatus, Request, Response},
     request::{Method, Router, RouterType},
     route,
-    sea_orm::{DatabaseConnection, TransactionTrait},
+    sea_orm::{
+        ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, QueryFilter,
+        TransactionTrait,
+    },
     service::{SKYNET_SERVICE, SResult, Service},
     uuid,
     viewer::permissions::PermissionViewer,
 };
-use skynet_api_task::{ID, viewer::tasks::TaskViewer};
+use skynet_api_task::{EventFilter, ID, TaskEvent, viewer::tasks::TaskViewer};
 
+mod acl;
 mod api;
+mod artifact;
+mod bundle;
+mod events;
+mod lock;
+mod logging;
 mod migration;
+mod notify;
+mod resolver;
+mod retention;
+mod secret;
 mod service;
+mod stats;
+mod stub;
+mod sync;
+mod visibility;
 
 include!(concat!(env!("OUT_DIR"), "/response.rs"));
 
@@ -49,7 +72,22 @@ include!(concat!(env!("OUT_DIR"), "/response.rs"));
     state: Default::default(),
     view_id: Default::default(),
     manage_id: Default::default(),
+    run_id: Default::default(),
+    edit_id: Default::default(),
+    code_id: Default::default(),
+    stop_id: Default::default(),
+    delete_id: Default::default(),
+    admin_id: Default::default(),
+    approve_id: Default::default(),
     script_handle: Default::default(),
+    secret: Default::default(),
+    runtime_path: Default::default(),
+    events: Default::default(),
+    sender: Default::default(),
+    queue: Default::default(),
+    start: Default::default(),
+    locks: Default::default(),
+    jobs: Default::default(),
 })]
 #[plugin_impl_root]
 #[plugin_impl_call(skynet_api::plugin::api::PluginApi, skynet_api_task::Service)]
@@ -59,7 +97,60 @@ struct Plugin {
     state: OnceLock<Data<GlobalState>>,
     view_id: OnceLock<HyUuid>,
     manage_id: OnceLock<HyUuid>,
+    run_id: OnceLock<HyUuid>,
+    edit_id: OnceLock<HyUuid>,
+    code_id: OnceLock<HyUuid>,
+    stop_id: OnceLock<HyUuid>,
+    delete_id: OnceLock<HyUuid>,
+    admin_id: OnceLock<HyUuid>,
+    approve_id: OnceLock<HyUuid>,
     script_handle: DashMap<HyUuid, bool>,
+    secret: OnceLock<SecretCipher>,
+    runtime_path: OnceLock<PathBuf>,
+    events: DashMap<String, EventFilter>,
+    /// Sender of the event delivery job, reset on unload.
+    sender: RwLock<Option<UnboundedSender<(Registry, TaskEvent)>>>,
+    queue: DashMap<HyUuid, Arc<Mutex<()>>>,
+    /// Per-script locks held from the concurrency check until the task is created.
+    start: DashMap<HyUuid, Arc<Mutex<()>>>,
+    locks: DashMap<String, Arc<Mutex<()>>>,
+    /// Background jobs aborted on unload.
+    jobs: DashMap<&'static str, JoinHandle<()>>,
+}
+
+/// Find or create permission `name`. A newly created permission is granted to the holders
+/// of `seed`, which guarded the same routes before, so that existing operators keep access.
+async fn init_permission(
+    tx: &DatabaseTransaction,
+    name: &str,
+    note: &str,
+    seed: HyUuid,
+) -> Result<HyUuid> {
+    if let Some(x) = permissions::Entity::find()
+        .filter(permissions::Column::Name.eq(name))
+        .one(tx)
+        .await?
+    {
+        return Ok(x.id);
+    }
+    let id = PermissionViewer::find_or_init(tx, name, note).await?.id;
+    for x in permission_links::Entity::find()
+        .filter(permission_links::Column::Pid.eq(seed))
+        .all(tx)
+        .await?
+    {
+        let perm = [PermEntry {
+            pid: id,
+            perm: x.perm,
+        }];
+        if let Some(uid) = x.uid {
+            PermissionViewer::create_user(tx, &uid, &perm).await?;
+        }
+        if let Some(gid) = x.gid {
+            PermissionViewer::create_group(tx, &gid, &perm).await?;
+        }
+    }
+    Ok(id)
 }
 
 #[plugin_impl_trait]
@@ -68,7 +159,7 @@ impl skynet_api::plugin::api::PluginApi for Plugin {
         &self,
         reg: &Registry,
         mut skynet: Skynet,
-        _runtime_path: PathBuf,
+        runtime_path: PathBuf,
     ) -> SResult<Skynet> {
         let server: Service = reg.get(SKYNET_SERVICE).unwrap().into();
         skynet.logger.plugin_start(server);
@@ -88,9 +179,75 @@ impl skynet_api::plugin::api::PluginApi for Plugin {
                 .await?
                 .id,
         );
+        let _ = self.run_id.set(
+            init_permission(
+                &tx,
+                &format!("run.{ID}"),
+                "plugin task script runner",
+                *self.manage_id.get().unwrap(),
+            )
+            .await?,
+        );
+        let _ = self.edit_id.set(
+            init_permission(
+                &tx,
+                &format!("edit.{ID}"),
+                "plugin task script editor",
+                *self.manage_id.get().unwrap(),
+            )
+            .await?,
+        );
+        let _ = self.code_id.set(
+            init_permission(
+                &tx,
+                &format!("code.{ID}"),
+                "plugin task script code viewer",
+                *self.manage_id.get().unwrap(),
+            )
+            .await?,
+        );
+        let _ = self.stop_id.set(
+            init_permission(
+                &tx,
+                &format!("stop.{ID}"),
+                "plugin task stopper",
+                *self.view_id.get().unwrap(),
+            )
+            .await?,
+        );
+        let _ = self.delete_id.set(
+            init_permission(
+                &tx,
+                &format!("delete.{ID}"),
+                "plugin task deleter",
+                *self.view_id.get().unwrap(),
+            )
+            .await?,
+        );
+        let _ = self.admin_id.set(
+            PermissionViewer::find_or_init(
+                &tx,
+                &format!("admin.{ID}"),
+                "plugin task administrator",
+            )
+            .await?
+            .id,
+        );
+        let _ = self.approve_id.set(
+            PermissionViewer::find_or_init(&tx, &format!("approve.{ID}"), "plugin task approver")
+                .await?
+                .id,
+        );
         tx.commit().await?;
 
         TaskViewer::clean_running(self.db.get().unwrap()).await?;
+        let _ = self
+            .secret
+            .set(SecretCipher::load(&runtime_path.join("secret.key"))?);
+        let _ = self.runtime_path.set(runtime_path);
+        self.jobs.insert("retention", retention::start());
+        self.jobs.insert("sync", sync::start());
+        self.jobs.insert("events", events::start());
 
         let _ = skynet.insert_menu(
             MenuItem {
@@ -110,7 +267,7 @@ impl skynet_api::plugin::api::PluginApi for Plugin {
                 plugin: Some(ID),
                 name: String::from("menu.task"),
                 path: format!("/plugin/{ID}/script"),
-                checker: PermChecker::new_entry(*self.manage_id.get().unwrap(), PERM_READ),
+                checker: PermChecker::new_entry(*self.code_id.get().unwrap(), PERM_READ),
                 ..Default::default()
             },
             1,
@@ -132,6 +289,13 @@ impl skynet_api::plugin::api::PluginApi for Plugin {
     async fn on_register(&self, _: &Registry, _skynet: Skynet, mut r: Vec<Router>) -> Vec<Router> {
         let view_id = *self.view_id.get().unwrap();
         let manage_id = *self.manage_id.get().unwrap();
+        let run_id = *self.run_id.get().unwrap();
+        let edit_id = *self.edit_id.get().unwrap();
+        let code_id = *self.code_id.get().unwrap();
+        let stop_id = *self.stop_id.get().unwrap();
+        let delete_id = *self.delete_id.get().unwrap();
+        let admin_id = *self.admin_id.get().unwrap();
+        let approve_id = *self.approve_id.get().unwrap();
         r.extend(vec![
             Router {
                 path: format!("/plugins/{ID}/tasks"),
@@ -144,7 +308,105 @@ impl skynet_api::plugin::api::PluginApi for Plugin {
                 path: format!("/plugins/{ID}/tasks"),
                 method: Method::Delete,
                 route: RouterType::Http(ID, String::from("api::delete_completed")),
-                checker: PermChecker::new_entry(view_id, PERM_WRITE),
+                checker: PermChecker::new_entry(delete_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/tasks/batch"),
+                method: Method::Delete,
+                route: RouterType::Http(ID, String::from("api::delete_task_batch")),
+                checker: PermChecker::new_entry(delete_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/tasks/{{tid}}"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_task")),
+                checker: PermChecker::new_entry(view_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/tasks/{{tid}}"),
+                method: Method::Delete,
+                route: RouterType::Http(ID, String::from("api::delete_task")),
+                checker: PermChecker::new_entry(delete_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/tasks/{{tid}}/rerun"),
+                method: Method::Post,
+                route: RouterType::Http(ID, String::from("api::rerun")),
+                checker: PermChecker::new_entry(run_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/stats"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_stats")),
+                checker: PermChecker::new_entry(view_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/metrics"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_metrics")),
+                checker: PermChecker::new_entry(view_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/metrics/scrape"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_metrics_scrape")),
+                checker: PermChecker::Entry(PermEntry::new_guest()),
+                csrf: CSRFType::Disabled,
+            },
+            Router {
+                path: format!("/plugins/{ID}/metrics/token"),
+                method: Method::Put,
+                route: RouterType::Http(ID, String::from("api::put_metrics_token")),
+                checker: PermChecker::new_entry(admin_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/retention"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_retention")),
+                checker: PermChecker::new_entry(view_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/retention"),
+                method: Method::Put,
+                route: RouterType::Http(ID, String::from("api::put_retention")),
+                checker: PermChecker::new_entry(delete_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/sync"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_sync")),
+                checker: PermChecker::new_entry(admin_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/sync"),
+                method: Method::Put,
+                route: RouterType::Http(ID, String::from("api::put_sync")),
+                checker: PermChecker::new_entry(admin_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/visibility"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_visibility")),
+                checker: PermChecker::new_entry(view_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/visibility"),
+                method: Method::Put,
+                route: RouterType::Http(ID, String::from("api::put_visibility")),
+                checker: PermChecker::new_entry(admin_id, PERM_WRITE),
                 csrf: CSRFType::Header,
             },
             Router {
@@ -154,11 +416,39 @@ impl skynet_api::plugin::api::PluginApi for Plugin {
                 checker: PermChecker::new_entry(view_id, PERM_READ),
                 csrf: CSRFType::Header,
             },
+            Router {
+                path: format!("/plugins/{ID}/tasks/{{tid}}/artifacts"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_artifacts")),
+                checker: PermChecker::new_entry(view_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/tasks/{{tid}}/artifacts/{{aid}}"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_artifact")),
+                checker: PermChecker::new_entry(view_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
             Router {
                 path: format!("/plugins/{ID}/tasks/{{tid}}/stop"),
                 method: Method::Post,
                 route: RouterType::Http(ID, String::from("api::stop")),
-                checker: PermChecker::new_entry(view_id, PERM_WRITE),
+                checker: PermChecker::new_entry(stop_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/tasks/{{tid}}/approve"),
+                method: Method::Post,
+                route: RouterType::Http(ID, String::from("api::approve_task")),
+                checker: PermChecker::new_entry(approve_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/tasks/{{tid}}/reject"),
+                method: Method::Post,
+                route: RouterType::Http(ID, String::from("api::reject_task")),
+                checker: PermChecker::new_entry(approve_id, PERM_WRITE),
                 csrf: CSRFType::Header,
             },
             Router {
@@ -172,41 +462,153 @@ impl skynet_api::plugin::api::PluginApi for Plugin {
                 path: format!("/plugins/{ID}/scripts/{{sid}}"),
                 method: Method::Get,
                 route: RouterType::Http(ID, String::from("api::get_script")),
-                checker: PermChecker::new_entry(manage_id, PERM_READ),
+                checker: PermChecker::new_entry(code_id, PERM_READ),
                 csrf: CSRFType::Header,
             },
             Router {
                 path: format!("/plugins/{ID}/scripts"),
                 method: Method::Post,
                 route: RouterType::Http(ID, String::from("api::add_script")),
-                checker: PermChecker::new_entry(manage_id, PERM_WRITE),
+                checker: PermChecker::new_entry(edit_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/scripts/validate"),
+                method: Method::Post,
+                route: RouterType::Http(ID, String::from("api::validate_script")),
+                checker: PermChecker::new_entry(edit_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/scripts/export"),
+                method: Method::Post,
+                route: RouterType::Http(ID, String::from("api::export_scripts")),
+                checker: PermChecker::new_entry(code_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/scripts/import"),
+                method: Method::Post,
+                route: RouterType::Http(ID, String::from("api::import_scripts")),
+                checker: PermChecker::new_entry(edit_id, PERM_WRITE),
                 csrf: CSRFType::Header,
             },
             Router {
                 path: format!("/plugins/{ID}/scripts/{{sid}}"),
                 method: Method::Put,
                 route: RouterType::Http(ID, String::from("api::put_script")),
-                checker: PermChecker::new_entry(manage_id, PERM_WRITE),
+                checker: PermChecker::new_entry(edit_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/scripts/{{sid}}/status"),
+                method: Method::Put,
+                route: RouterType::Http(ID, String::from("api::put_script_status")),
+                checker: PermChecker::new_entry(edit_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/scripts/{{sid}}/acl"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_script_acl")),
+                checker: PermChecker::new_entry(edit_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/scripts/{{sid}}/acl"),
+                method: Method::Put,
+                route: RouterType::Http(ID, String::from("api::put_script_acl")),
+                checker: PermChecker::new_entry(edit_id, PERM_WRITE),
                 csrf: CSRFType::Header,
             },
             Router {
                 path: format!("/plugins/{ID}/scripts"),
                 method: Method::Delete,
                 route: RouterType::Http(ID, String::from("api::delete_script_batch")),
-                checker: PermChecker::new_entry(manage_id, PERM_WRITE),
+                checker: PermChecker::new_entry(edit_id, PERM_WRITE),
                 csrf: CSRFType::Header,
             },
             Router {
                 path: format!("/plugins/{ID}/scripts/{{sid}}"),
                 method: Method::Delete,
                 route: RouterType::Http(ID, String::from("api::delete_script")),
-                checker: PermChecker::new_entry(manage_id, PERM_WRITE),
+                checker: PermChecker::new_entry(edit_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/scripts/{{sid}}/state"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_state")),
+                checker: PermChecker::new_entry(manage_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/scripts/{{sid}}/state"),
+                method: Method::Delete,
+                route: RouterType::Http(ID, String::from("api::reset_state")),
+                checker: PermChecker::new_entry(edit_id, PERM_WRITE),
                 csrf: CSRFType::Header,
             },
             Router {
                 path: format!("/plugins/{ID}/scripts/{{sid}}/run"),
                 method: Method::Post,
                 route: RouterType::Http(ID, String::from("api::run_script")),
+                checker: PermChecker::new_entry(run_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/secrets"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_secrets")),
+                checker: PermChecker::new_entry(manage_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/secrets"),
+                method: Method::Post,
+                route: RouterType::Http(ID, String::from("api::add_secret")),
+                checker: PermChecker::new_entry(manage_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/secrets/{{id}}"),
+                method: Method::Put,
+                route: RouterType::Http(ID, String::from("api::put_secret")),
+                checker: PermChecker::new_entry(manage_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/secrets/{{id}}"),
+                method: Method::Delete,
+                route: RouterType::Http(ID, String::from("api::delete_secret")),
+                checker: PermChecker::new_entry(manage_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/notifications"),
+                method: Method::Get,
+                route: RouterType::Http(ID, String::from("api::get_notifications")),
+                checker: PermChecker::new_entry(manage_id, PERM_READ),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/notifications"),
+                method: Method::Post,
+                route: RouterType::Http(ID, String::from("api::add_notification")),
+                checker: PermChecker::new_entry(manage_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/notifications/{{id}}"),
+                method: Method::Put,
+                route: RouterType::Http(ID, String::from("api::put_notification")),
+                checker: PermChecker::new_entry(manage_id, PERM_WRITE),
+                csrf: CSRFType::Header,
+            },
+            Router {
+                path: format!("/plugins/{ID}/notifications/{{id}}"),
+                method: Method::Delete,
+                route: RouterType::Http(ID, String::from("api::delete_notification")),
                 checker: PermChecker::new_entry(manage_id, PERM_WRITE),
                 csrf: CSRFType::Header,
             },
@@ -218,15 +620,49 @@ impl skynet_api::plugin::api::PluginApi for Plugin {
         route!(reg, self.state.get().unwrap(), name, req,
             "api::get_tasks" => api::get_tasks,
             "api::delete_completed" => api::delete_completed,
+            "api::delete_task_batch" => api::delete_task_batch,
+            "api::get_task" => api::get_task,
+            "api::delete_task" => api::delete_task,
+            "api::rerun" => api::rerun,
+            "api::get_stats" => api::get_stats,
+            "api::get_metrics" => api::get_metrics,
+            "api::get_metrics_scrape" => api::get_metrics_scrape,
+            "api::put_metrics_token" => api::put_metrics_token,
+            "api::get_retention" => api::get_retention,
+            "api::put_retention" => api::put_retention,
+            "api::get_sync" => api::get_sync,
+            "api::put_sync" => api::put_sync,
+            "api::get_visibility" => api::get_visibility,
+            "api::put_visibility" => api::put_visibility,
             "api::get_output" => api::get_output,
+            "api::get_artifacts" => api::get_artifacts,
+            "api::get_artifact" => api::get_artifact,
             "api::stop" => api::stop,
+            "api::approve_task" => api::approve_task,
+            "api::reject_task" => api::reject_task,
             "api::get_scripts" => api::get_scripts,
             "api::get_script" => api::get_script,
             "api::add_script" => api::add_script,
+            "api::validate_script" => api::validate_script,
+            "api::export_scripts" => api::export_scripts,
+            "api::import_scripts" => api::import_scripts,
             "api::put_script" => api::put_script,
+            "api::put_script_status" => api::put_script_status,
+            "api::get_script_acl" => api::get_script_acl,
+            "api::put_script_acl" => api::put_script_acl,
             "api::delete_script_batch" => api::delete_script_batch,
             "api::delete_script" => api::delete_script,
+            "api::get_state" => api::get_state,
+            "api::reset_state" => api::reset_state,
             "api::run_script" => api::run_script,
+            "api::get_secrets" => api::get_secrets,
+            "api::add_secret" => api::add_secret,
+            "api::put_secret" => api::put_secret,
+            "api::delete_secret" => api::delete_secret,
+            "api::get_notifications" => api::get_notifications,
+            "api::add_notification" => api::add_notification,
+            "api::put_notification" => api::put_notification,
+            "api::delete_notification" => api::delete_notification,
         )
     }
 
@@ -234,5 +670,11 @@ impl skynet_api::plugin::api::PluginApi for Plugin {
         self.state.get().unwrap().locale.translate(lang, str)
     }
 
-    async fn on_unload(&self, _: &Registry, _status: PluginStatus) {}
+    async fn on_unload(&self, _: &Registry, _status: PluginStatus) {
+        for x in self.jobs.iter() {
+            x.abort();
+        }
+        self.jobs.clear();
+        *self.sender.write().unwrap() = None;
+    }
 }
@@ -0,0 +1,77 @@
+use std::{hash::Hash, sync::Arc};
+
+use actix_cloud::tokio::sync::{Mutex, OwnedMutexGuard};
+use dashmap::DashMap;
+
+/// Handle to the lock `key` in a lock map. The entry is removed from the map when the last
+/// handle is dropped, so that the map only keeps locks being held or waited for.
+pub struct LockHandle<K: Eq + Hash + 'static> {
+    map: &'static DashMap<K, Arc<Mutex<()>>>,
+    key: K,
+    lock: Option<Arc<Mutex<()>>>,
+}
+
+impl<K: Eq + Hash + Clone + 'static> LockHandle<K> {
+    /// Get the lock `key` in `map`, creating it if not exist.
+    pub fn new(map: &'static DashMap<K, Arc<Mutex<()>>>, key: K) -> Self {
+        let lock = map.entry(key.clone()).or_default().clone();
+        Self {
+            map,
+            key,
+            lock: Some(lock),
+        }
+    }
+}
+
+impl<K: Eq + Hash + 'static> LockHandle<K> {
+    /// Wait for the lock.
+    pub async fn lock(self) -> LockGuard<K> {
+        let guard = self.lock.clone().unwrap().lock_owned().await;
+        LockGuard {
+            _guard: guard,
+            _handle: self,
+        }
+    }
+}
+
+impl<K: Eq + Hash + 'static> Drop for LockHandle<K> {
+    fn drop(&mut self) {
+        drop(self.lock.take());
+        // Removed under the shard lock, nobody can clone the lock concurrently.
+        self.map
+            .remove_if(&self.key, |_, x| Arc::strong_count(x) == 1);
+    }
+}
+
+/// Guard of a held lock, releasing the lock before its handle on drop.
+pub struct LockGuard<K: Eq + Hash + 'static> {
+    _guard: OwnedMutexGuard<()>,
+    _handle: LockHandle<K>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::LazyLock;
+
+    use actix_cloud::tokio::runtime;
+
+    use super::*;
+
+    static LOCKS: LazyLock<DashMap<String, Arc<Mutex<()>>>> = LazyLock::new(DashMap::new);
+
+    #[test]
+    fn remove_unused() {
+        let rt = runtime::Builder::new_current_thread().build().unwrap();
+        let key = String::from("a");
+        let guard = rt.block_on(LockHandle::new(&LOCKS, key.clone()).lock());
+        let waiter = LockHandle::new(&LOCKS, key.clone());
+        drop(guard);
+        assert!(LOCKS.contains_key(&key));
+        let guard = rt.block_on(waiter.lock());
+        assert!(LOCKS.contains_key(&key));
+        drop(guard);
+        assert!(!LOCKS.contains_key(&key));
+        drop(LockHandle::new(&LOCKS, key.clone()));
+        assert!(!LOCKS.contains_key(&key));
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use actix_cloud::chrono::{SecondsFormat, Utc};
+use serde::Deserialize;
+
+/// Level of log records written by scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    const ALL: [Self; 4] = [Self::Debug, Self::Info, Self::Warn, Self::Error];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Format a log record as task output, e.g. `[2024-01-01T00:00:00.000Z] [INFO] message`.
+/// Continuation lines of multi-line messages are indented.
+pub fn format_record(level: LogLevel, message: &str) -> String {
+    format!(
+        "[{}] [{level}] {}\n",
+        Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        message.trim_end_matches('\n').replace('\n', "\n  ")
+    )
+}
+
+/// Parse the level of an output line, `None` for continuation lines.
+/// Plain output written by `task_update` is treated as info.
+fn parse_level(line: &str) -> Option<LogLevel> {
+    if line.starts_with("  ") {
+        return None;
+    }
+    let level = line
+        .strip_prefix('[')
+        .and_then(|x| x.split_once("] ["))
+        .and_then(|(_, x)| x.split_once(']'))
+        .and_then(|(x, _)| LogLevel::ALL.into_iter().find(|l| l.as_str() == x));
+    Some(level.unwrap_or(LogLevel::Info))
+}
+
+/// Keep only the output lines with at least `level`.
+pub fn filter_output(output: &str, level: LogLevel) -> String {
+    let mut keep = true;
+    output
+        .split_inclusive('\n')
+        .filter(|x| {
+            if let Some(l) = parse_level(x) {
+                keep = l >= level;
+            }
+            keep
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_level_of_lines() {
+        assert_eq!(
+            parse_level("[2024-01-01T00:00:00.000Z] [WARN] disk full\n"),
+            Some(LogLevel::Warn)
+        );
+        assert_eq!(
+            parse_level("[2024-01-01T00:00:00.000Z] [DEBUG] x"),
+            Some(LogLevel::Debug)
+        );
+        assert_eq!(parse_level("plain output\n"), Some(LogLevel::Info));
+        assert_eq!(
+            parse_level("[2024-01-01T00:00:00.000Z] [TRACE] x"),
+            Some(LogLevel::Info)
+        );
+        assert_eq!(parse_level("  continued\n"), None);
+    }
+
+    #[test]
+    fn filter_output_by_level() {
+        let output = [
+            format_record(LogLevel::Debug, "a"),
+            format_record(LogLevel::Error, "b\nc"),
+            String::from("plain\n"),
+            format_record(LogLevel::Warn, "d"),
+        ]
+        .concat();
+        let ret = filter_output(&output, LogLevel::Warn);
+        assert!(!ret.contains("] a"));
+        assert!(ret.contains("[ERROR] b\n  c\n"));
+        assert!(!ret.contains("plain"));
+        assert!(ret.contains("[WARN] d\n"));
+        assert_eq!(filter_output(&output, LogLevel::Debug), output);
+        assert_eq!(filter_output("", LogLevel::Info), "");
+    }
+
+    #[test]
+    fn format_record_indents_continuation() {
+        let ret = format_record(LogLevel::Info, "a\nb\n");
+        assert!(ret.ends_with("[INFO] a\n  b\n"));
+    }
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Tasks {
+    Table,
+    DryRun,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .add_column(
+                        ColumnDef::new(Tasks::DryRun)
+                            .boolean()
+                            .default(false)
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .drop_column(Tasks::DryRun)
+                    .to_owned(),
+            )
+            .await
+    }
+}
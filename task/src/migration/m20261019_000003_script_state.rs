@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum States {
+    Table,
+    ID,
+    Sid,
+    Key,
+    Value,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Scripts {
+    Table,
+    ID,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(table_prefix(&States::Table))
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(States::ID)
+                            .char_len(36)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(States::Sid).char_len(36).not_null())
+                    .col(ColumnDef::new(States::Key).string_len(128).not_null())
+                    .col(ColumnDef::new(States::Value).string().not_null())
+                    .col(ColumnDef::new(States::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(States::UpdatedAt).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(table_prefix(&Scripts::Table), Scripts::ID)
+                            .from_col(States::Sid)
+                            .on_update(ForeignKeyAction::Restrict)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name(table_prefix(&Alias::new("states_sid_key")).to_string())
+                    .table(table_prefix(&States::Table))
+                    .col(States::Sid)
+                    .col(States::Key)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(table_prefix(&States::Table)).to_owned())
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Secrets {
+    Table,
+    ID,
+    Name,
+    Value,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(table_prefix(&Secrets::Table))
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Secrets::ID)
+                            .char_len(36)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Secrets::Name)
+                            .string_len(64)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Secrets::Value).string().not_null())
+                    .col(ColumnDef::new(Secrets::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(Secrets::UpdatedAt).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(table_prefix(&Secrets::Table))
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Artifacts {
+    Table,
+    ID,
+    Tid,
+    Name,
+    Size,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Tasks {
+    Table,
+    ID,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(table_prefix(&Artifacts::Table))
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Artifacts::ID)
+                            .char_len(36)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Artifacts::Tid).char_len(36).not_null())
+                    .col(ColumnDef::new(Artifacts::Name).string_len(256).not_null())
+                    .col(ColumnDef::new(Artifacts::Size).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Artifacts::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Artifacts::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(table_prefix(&Tasks::Table), Tasks::ID)
+                            .from_col(Artifacts::Tid)
+                            .on_update(ForeignKeyAction::Restrict)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(table_prefix(&Artifacts::Table))
+                    .to_owned(),
+            )
+            .await
+    }
+}
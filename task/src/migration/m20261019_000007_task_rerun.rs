@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Tasks {
+    Table,
+    Code,
+    Params,
+    Parent,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per statement.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .add_column(ColumnDef::new(Tasks::Code).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .add_column(ColumnDef::new(Tasks::Params).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .add_column(ColumnDef::new(Tasks::Parent).char_len(36))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for x in [Tasks::Code, Tasks::Params, Tasks::Parent] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table_prefix(&Tasks::Table))
+                        .drop_column(x)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
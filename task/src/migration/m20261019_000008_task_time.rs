@@ -0,0 +1,80 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Tasks {
+    Table,
+    Uid,
+    Result,
+    StartedAt,
+    FinishedAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per statement.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .add_column(ColumnDef::new(Tasks::Uid).char_len(36))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .add_column(ColumnDef::new(Tasks::StartedAt).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .add_column(ColumnDef::new(Tasks::FinishedAt).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(table_prefix(&Tasks::Table))
+                    .value(Tasks::StartedAt, Expr::col(Tasks::CreatedAt))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(table_prefix(&Tasks::Table))
+                    .value(Tasks::FinishedAt, Expr::col(Tasks::UpdatedAt))
+                    .and_where(Expr::col(Tasks::Result).is_not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for x in [Tasks::Uid, Tasks::StartedAt, Tasks::FinishedAt] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table_prefix(&Tasks::Table))
+                        .drop_column(x)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Notifications {
+    Table,
+    ID,
+    Name,
+    Url,
+    Callback,
+    Sid,
+    OnSuccess,
+    OnFailure,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Scripts {
+    Table,
+    ID,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(table_prefix(&Notifications::Table))
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Notifications::ID)
+                            .char_len(36)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Notifications::Name)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Notifications::Url).string_len(1024))
+                    .col(ColumnDef::new(Notifications::Callback).string_len(256))
+                    .col(ColumnDef::new(Notifications::Sid).char_len(36))
+                    .col(
+                        ColumnDef::new(Notifications::OnSuccess)
+                            .boolean()
+                            .default(false)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Notifications::OnFailure)
+                            .boolean()
+                            .default(true)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Notifications::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Notifications::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(table_prefix(&Scripts::Table), Scripts::ID)
+                            .from_col(Notifications::Sid)
+                            .on_update(ForeignKeyAction::Restrict)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(table_prefix(&Notifications::Table))
+                    .to_owned(),
+            )
+            .await
+    }
+}
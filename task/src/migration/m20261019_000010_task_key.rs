@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Tasks {
+    Table,
+    Plugin,
+    IdempotencyKey,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per statement.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .add_column(ColumnDef::new(Tasks::Plugin).char_len(36))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .add_column(ColumnDef::new(Tasks::IdempotencyKey).string_len(64))
+                    .to_owned(),
+            )
+            .await?;
+        // Keys are scoped by the creating plugin.
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name(table_prefix(&Alias::new("tasks_plugin_idempotency_key")).to_string())
+                    .table(table_prefix(&Tasks::Table))
+                    .col(Tasks::Plugin)
+                    .col(Tasks::IdempotencyKey)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(table_prefix(&Alias::new("tasks_plugin_idempotency_key")).to_string())
+                    .table(table_prefix(&Tasks::Table))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .drop_column(Tasks::IdempotencyKey)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .drop_column(Tasks::Plugin)
+                    .to_owned(),
+            )
+            .await
+    }
+}
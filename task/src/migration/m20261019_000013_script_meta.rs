@@ -0,0 +1,128 @@
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Scripts {
+    Table,
+    ID,
+    Name,
+    Description,
+    Folder,
+}
+
+#[derive(Iden)]
+enum Tasks {
+    Table,
+    Name,
+}
+
+#[derive(Iden)]
+enum Tags {
+    Table,
+    ID,
+    Sid,
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per statement.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Scripts::Table))
+                    .add_column(ColumnDef::new(Scripts::Description).string_len(1024))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Scripts::Table))
+                    .add_column(ColumnDef::new(Scripts::Folder).string_len(256))
+                    .to_owned(),
+            )
+            .await?;
+        // SQLite does not enforce the length of strings.
+        if manager.get_database_backend() != DatabaseBackend::Sqlite {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table_prefix(&Scripts::Table))
+                        .modify_column(ColumnDef::new(Scripts::Name).string_len(128).not_null())
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table_prefix(&Tasks::Table))
+                        .modify_column(ColumnDef::new(Tasks::Name).string_len(256).not_null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager
+            .create_table(
+                Table::create()
+                    .table(table_prefix(&Tags::Table))
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Tags::ID)
+                            .char_len(36)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Tags::Sid).char_len(36).not_null())
+                    .col(ColumnDef::new(Tags::Name).string_len(64).not_null())
+                    .col(ColumnDef::new(Tags::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(Tags::UpdatedAt).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(table_prefix(&Scripts::Table), Scripts::ID)
+                            .from_col(Tags::Sid)
+                            .on_update(ForeignKeyAction::Restrict)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name(table_prefix(&Alias::new("tags_sid_name")).to_string())
+                    .table(table_prefix(&Tags::Table))
+                    .col(Tags::Sid)
+                    .col(Tags::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(table_prefix(&Tags::Table)).to_owned())
+            .await?;
+        for x in [Scripts::Description, Scripts::Folder] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table_prefix(&Scripts::Table))
+                        .drop_column(x)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Scripts {
+    Table,
+    Enabled,
+    ArchivedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per statement.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Scripts::Table))
+                    .add_column(
+                        ColumnDef::new(Scripts::Enabled)
+                            .boolean()
+                            .default(true)
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Scripts::Table))
+                    .add_column(ColumnDef::new(Scripts::ArchivedAt).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for x in [Scripts::Enabled, Scripts::ArchivedAt] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table_prefix(&Scripts::Table))
+                        .drop_column(x)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, DatabaseBackend, Statement, TransactionTrait},
+};
+
+use super::migrator::table_prefix;
+
+#[derive(Iden, Clone, Copy)]
+enum Tasks {
+    Table,
+    ID,
+    Name,
+    Detail,
+    Output,
+    Result,
+    Percent,
+    Sid,
+    DryRun,
+    Code,
+    Params,
+    Parent,
+    Uid,
+    StartedAt,
+    FinishedAt,
+    IdempotencyKey,
+    ScriptName,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Scripts {
+    Table,
+    ID,
+    Name,
+}
+
+const COLUMNS: [Tasks; 18] = [
+    Tasks::ID,
+    Tasks::Name,
+    Tasks::Detail,
+    Tasks::Output,
+    Tasks::Result,
+    Tasks::Percent,
+    Tasks::Sid,
+    Tasks::DryRun,
+    Tasks::Code,
+    Tasks::Params,
+    Tasks::Parent,
+    Tasks::Uid,
+    Tasks::StartedAt,
+    Tasks::FinishedAt,
+    Tasks::IdempotencyKey,
+    Tasks::ScriptName,
+    Tasks::CreatedAt,
+    Tasks::UpdatedAt,
+];
+
+fn foreign_key_name() -> String {
+    table_prefix(&Alias::new("tasks_sid")).to_string()
+}
+
+fn key_index() -> IndexCreateStatement {
+    Index::create()
+        .if_not_exists()
+        .name(table_prefix(&Alias::new("tasks_idempotency_key")).to_string())
+        .table(table_prefix(&Tasks::Table))
+        .col(Tasks::IdempotencyKey)
+        .unique()
+        .to_owned()
+}
+
+fn tasks_table(on_delete: ForeignKeyAction) -> TableCreateStatement {
+    Table::create()
+        .table(table_prefix(&Tasks::Table))
+        .col(
+            ColumnDef::new(Tasks::ID)
+                .char_len(36)
+                .not_null()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Tasks::Name).string_len(256).not_null())
+        .col(ColumnDef::new(Tasks::Detail).string_len(1024))
+        .col(ColumnDef::new(Tasks::Output).string())
+        .col(ColumnDef::new(Tasks::Result).integer())
+        .col(
+            ColumnDef::new(Tasks::Percent)
+                .integer()
+                .default(0)
+                .not_null(),
+        )
+        .col(ColumnDef::new(Tasks::Sid).char_len(36))
+        .col(
+            ColumnDef::new(Tasks::DryRun)
+                .boolean()
+                .default(false)
+                .not_null(),
+        )
+        .col(ColumnDef::new(Tasks::Code).string())
+        .col(ColumnDef::new(Tasks::Params).string())
+        .col(ColumnDef::new(Tasks::Parent).char_len(36))
+        .col(ColumnDef::new(Tasks::Uid).char_len(36))
+        .col(ColumnDef::new(Tasks::StartedAt).big_integer())
+        .col(ColumnDef::new(Tasks::FinishedAt).big_integer())
+        .col(ColumnDef::new(Tasks::IdempotencyKey).string_len(64))
+        .col(ColumnDef::new(Tasks::ScriptName).string_len(128))
+        .col(ColumnDef::new(Tasks::CreatedAt).big_integer().not_null())
+        .col(ColumnDef::new(Tasks::UpdatedAt).big_integer().not_null())
+        .foreign_key(
+            ForeignKey::create()
+                .name(foreign_key_name())
+                .to(table_prefix(&Scripts::Table), Scripts::ID)
+                .from_col(Tasks::Sid)
+                .on_update(ForeignKeyAction::Restrict)
+                .on_delete(on_delete),
+        )
+        .to_owned()
+}
+
+/// SQLite cannot alter foreign keys, so the tasks table is rebuilt.
+async fn rebuild(manager: &SchemaManager<'_>, on_delete: ForeignKeyAction) -> Result<(), DbErr> {
+    let tx = manager.get_connection().begin().await?;
+    let old = table_prefix(&Alias::new("tasks_old"));
+    // Keep foreign keys of artifacts pointing at the tasks table while renaming,
+    // so that dropping the old table does not cascade.
+    tx.execute_unprepared("PRAGMA legacy_alter_table = ON")
+        .await?;
+    let m = SchemaManager::new(&tx);
+    m.drop_index(
+        Index::drop()
+            .name(table_prefix(&Alias::new("tasks_idempotency_key")).to_string())
+            .table(table_prefix(&Tasks::Table))
+            .to_owned(),
+    )
+    .await?;
+    m.rename_table(
+        Table::rename()
+            .table(table_prefix(&Tasks::Table), old.clone())
+            .to_owned(),
+    )
+    .await?;
+    m.create_table(tasks_table(on_delete)).await?;
+    m.exec_stmt(
+        Query::insert()
+            .into_table(table_prefix(&Tasks::Table))
+            .columns(COLUMNS)
+            .select_from(
+                Query::select()
+                    .columns(COLUMNS)
+                    .from(old.clone())
+                    .to_owned(),
+            )
+            .map_err(|e| DbErr::Migration(e.to_string()))?
+            .to_owned(),
+    )
+    .await?;
+    m.drop_table(Table::drop().table(old).to_owned()).await?;
+    m.create_index(key_index()).await?;
+    tx.execute_unprepared("PRAGMA legacy_alter_table = OFF")
+        .await?;
+    tx.commit().await
+}
+
+/// Replace the foreign key of `tasks.sid` on PostgreSQL and MySQL.
+async fn replace(manager: &SchemaManager<'_>, on_delete: ForeignKeyAction) -> Result<(), DbErr> {
+    let backend = manager.get_database_backend();
+    let sql = match backend {
+        DatabaseBackend::Postgres => {
+            "SELECT tc.constraint_name FROM information_schema.table_constraints tc \
+             JOIN information_schema.key_column_usage kcu \
+             ON tc.constraint_name = kcu.constraint_name AND tc.table_schema = kcu.table_schema \
+             WHERE tc.constraint_type = 'FOREIGN KEY' AND tc.table_schema = current_schema() \
+             AND tc.table_name = $1 AND kcu.column_name = 'sid'"
+        }
+        _ => {
+            "SELECT tc.CONSTRAINT_NAME FROM information_schema.TABLE_CONSTRAINTS tc \
+             JOIN information_schema.KEY_COLUMN_USAGE kcu \
+             ON tc.CONSTRAINT_NAME = kcu.CONSTRAINT_NAME AND tc.TABLE_SCHEMA = kcu.TABLE_SCHEMA \
+             AND tc.TABLE_NAME = kcu.TABLE_NAME \
+             WHERE tc.CONSTRAINT_TYPE = 'FOREIGN KEY' AND tc.TABLE_SCHEMA = DATABASE() \
+             AND tc.TABLE_NAME = ? AND kcu.COLUMN_NAME = 'sid'"
+        }
+    };
+    let name = manager
+        .get_connection()
+        .query_one(Statement::from_sql_and_values(
+            backend,
+            sql,
+            [table_prefix(&Tasks::Table).to_string().into()],
+        ))
+        .await?
+        .map(|x| x.try_get_by_index::<String>(0))
+        .transpose()?;
+    if let Some(name) = name {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name(name)
+                    .table(table_prefix(&Tasks::Table))
+                    .to_owned(),
+            )
+            .await?;
+    }
+    manager
+        .create_foreign_key(
+            ForeignKey::create()
+                .name(foreign_key_name())
+                .from(table_prefix(&Tasks::Table), Tasks::Sid)
+                .to(table_prefix(&Scripts::Table), Scripts::ID)
+                .on_update(ForeignKeyAction::Restrict)
+                .on_delete(on_delete)
+                .to_owned(),
+        )
+        .await
+}
+
+async fn foreign_key(
+    manager: &SchemaManager<'_>,
+    on_delete: ForeignKeyAction,
+) -> Result<(), DbErr> {
+    if manager.get_database_backend() == DatabaseBackend::Sqlite {
+        rebuild(manager, on_delete).await
+    } else {
+        replace(manager, on_delete).await
+    }
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .add_column(ColumnDef::new(Tasks::ScriptName).string_len(128))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(table_prefix(&Tasks::Table))
+                    .value(
+                        Tasks::ScriptName,
+                        SimpleExpr::SubQuery(
+                            None,
+                            Box::new(
+                                Query::select()
+                                    .column(Scripts::Name)
+                                    .from(table_prefix(&Scripts::Table))
+                                    .and_where(
+                                        Expr::col((table_prefix(&Scripts::Table), Scripts::ID))
+                                            .equals((table_prefix(&Tasks::Table), Tasks::Sid)),
+                                    )
+                                    .to_owned()
+                                    .into_sub_query_statement(),
+                            ),
+                        ),
+                    )
+                    .and_where(Expr::col(Tasks::Sid).is_not_null())
+                    .to_owned(),
+            )
+            .await?;
+        foreign_key(manager, ForeignKeyAction::SetNull).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        foreign_key(manager, ForeignKeyAction::Cascade).await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Tasks::Table))
+                    .drop_column(Tasks::ScriptName)
+                    .to_owned(),
+            )
+            .await
+    }
+}
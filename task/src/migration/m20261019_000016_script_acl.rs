@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Acls {
+    Table,
+    ID,
+    Sid,
+    Principal,
+    Group,
+    Perm,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Scripts {
+    Table,
+    ID,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(table_prefix(&Acls::Table))
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Acls::ID)
+                            .char_len(36)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Acls::Sid).char_len(36).not_null())
+                    .col(ColumnDef::new(Acls::Principal).char_len(36).not_null())
+                    .col(
+                        ColumnDef::new(Acls::Group)
+                            .boolean()
+                            .default(false)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Acls::Perm).integer().not_null())
+                    .col(ColumnDef::new(Acls::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(Acls::UpdatedAt).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(table_prefix(&Scripts::Table), Scripts::ID)
+                            .from_col(Acls::Sid)
+                            .on_update(ForeignKeyAction::Restrict)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name(table_prefix(&Alias::new("acls_sid_principal")).to_string())
+                    .table(table_prefix(&Acls::Table))
+                    .col(Acls::Sid)
+                    .col(Acls::Principal)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(table_prefix(&Acls::Table)).to_owned())
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+use super::migrator::table_prefix;
+
+#[derive(Iden)]
+enum Scripts {
+    Table,
+    Approval,
+}
+
+#[derive(Iden)]
+enum Tasks {
+    Table,
+    Approval,
+    Approver,
+    ApprovalReason,
+    DecidedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Scripts::Table))
+                    .add_column(
+                        ColumnDef::new(Scripts::Approval)
+                            .boolean()
+                            .default(false)
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // SQLite only supports one column per statement.
+        for x in [
+            ColumnDef::new(Tasks::Approval).integer().to_owned(),
+            ColumnDef::new(Tasks::Approver).char_len(36).to_owned(),
+            ColumnDef::new(Tasks::ApprovalReason)
+                .string_len(256)
+                .to_owned(),
+            ColumnDef::new(Tasks::DecidedAt).big_integer().to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table_prefix(&Tasks::Table))
+                        .add_column(x)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for x in [
+            Tasks::Approval,
+            Tasks::Approver,
+            Tasks::ApprovalReason,
+            Tasks::DecidedAt,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table_prefix(&Tasks::Table))
+                        .drop_column(x)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(table_prefix(&Scripts::Table))
+                    .drop_column(Scripts::Approval)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
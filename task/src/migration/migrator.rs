@@ -8,7 +8,26 @@ pub struct Migrator;
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20240101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20240101_000001_create_table::Migration),
+            Box::new(m20261019_000001_task_dry_run::Migration),
+            Box::new(m20261019_000002_script_library::Migration),
+            Box::new(m20261019_000003_script_state::Migration),
+            Box::new(m20261019_000004_secret::Migration),
+            Box::new(m20261019_000005_task_artifact::Migration),
+            Box::new(m20261019_000006_setting::Migration),
+            Box::new(m20261019_000007_task_rerun::Migration),
+            Box::new(m20261019_000008_task_time::Migration),
+            Box::new(m20261019_000009_notification::Migration),
+            Box::new(m20261019_000010_task_key::Migration),
+            Box::new(m20261019_000011_script_concurrency::Migration),
+            Box::new(m20261019_000012_script_source::Migration),
+            Box::new(m20261019_000013_script_meta::Migration),
+            Box::new(m20261019_000014_script_status::Migration),
+            Box::new(m20261019_000015_task_script_fk::Migration),
+            Box::new(m20261019_000016_script_acl::Migration),
+            Box::new(m20261019_000017_task_approval::Migration),
+        ]
     }
 
     fn migration_table_name() -> DynIden {
@@ -1,2 +1,19 @@
 mod m20240101_000001_create_table;
+mod m20261019_000001_task_dry_run;
+mod m20261019_000002_script_library;
+mod m20261019_000003_script_state;
+mod m20261019_000004_secret;
+mod m20261019_000005_task_artifact;
+mod m20261019_000006_setting;
+mod m20261019_000007_task_rerun;
+mod m20261019_000008_task_time;
+mod m20261019_000009_notification;
+mod m20261019_000010_task_key;
+mod m20261019_000011_script_concurrency;
+mod m20261019_000012_script_source;
+mod m20261019_000013_script_meta;
+mod m20261019_000014_script_status;
+mod m20261019_000015_task_script_fk;
+mod m20261019_000016_script_acl;
+mod m20261019_000017_task_approval;
 pub mod migrator;
@@ -0,0 +1,82 @@
+use std::{sync::LazyLock, time::Duration};
+
+use actix_cloud::{
+    tokio::runtime,
+    tracing::{error, info},
+};
+use serde::Serialize;
+use skynet_api::{HyUuid, Result, ffi_rpc::registry::Registry};
+use skynet_api_task::{
+    TaskCallback,
+    entity::tasks,
+    viewer::{notifications::NotificationViewer, tasks::TaskViewer},
+};
+
+use crate::{PLUGIN_INSTANCE, api::TaskStatus};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+static CLIENT: LazyLock<reqwest::Client> =
+    LazyLock::new(|| reqwest::Client::builder().timeout(TIMEOUT).build().unwrap());
+
+/// Webhook payload of a finished task.
+#[derive(Serialize)]
+struct Payload {
+    #[serde(flatten)]
+    task: tasks::Model,
+    status: TaskStatus,
+}
+
+/// Fire notification rules matching finished task `id`.
+pub async fn finish(reg: &Registry, id: &HyUuid) -> Result<()> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let Some(mut task) = TaskViewer::find_by_id(db, id).await? else {
+        return Ok(());
+    };
+    let Some(result) = task.result else {
+        return Ok(());
+    };
+    if task.dry_run {
+        return Ok(());
+    }
+    let rules = NotificationViewer::find_match(db, task.sid, result == 0).await?;
+    if rules.is_empty() {
+        return Ok(());
+    }
+    task.output = None;
+    let payload = Payload {
+        status: TaskStatus::of(&task),
+        task,
+    };
+    for rule in rules {
+        if let Some(url) = rule.url {
+            let body = serde_json::to_vec(&payload)?;
+            let (rid, tid) = (rule.id, *id);
+            runtime::Handle::current().spawn(async move {
+                match CLIENT
+                    .post(&url)
+                    .header("Content-Type", "application/json")
+                    .body(body)
+                    .send()
+                    .await
+                    .and_then(|x| x.error_for_status())
+                {
+                    Ok(_) => info!(success = true, id = %rid, task = %tid, "Notify webhook"),
+                    Err(e) => {
+                        error!(success = false, id = %rid, task = %tid, error = %e, "Notify webhook")
+                    }
+                }
+            });
+        }
+        if let Some(cb) = rule.callback {
+            match reg.get(&cb) {
+                Some(x) => {
+                    let x: TaskCallback = x.into();
+                    x.on_finish(reg, id, &result).await;
+                }
+                None => error!(success = false, id = %rule.id, callback = cb, "Callback not exist"),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use std::{collections::BTreeMap, sync::RwLock};
+
+use actix_cloud::tokio::runtime;
+use rhai::{Engine, EvalAltResult, Module, ModuleResolver, Position, Scope, Shared};
+use skynet_api::HyUuid;
+use skynet_api_task::viewer::{acls::ACL_RUN, scripts::ScriptViewer};
+
+use crate::{PLUGIN_INSTANCE, acl};
+
+/// Resolve `import "name" as x;` from enabled library scripts stored in the database.
+#[derive(Default)]
+pub struct ScriptResolver {
+    /// User running the script, libraries restricted by ACL require its run right.
+    /// Tasks without user, e.g. created by plugins, are not restricted.
+    uid: Option<HyUuid>,
+    cache: RwLock<BTreeMap<String, Shared<Module>>>,
+    loading: RwLock<Vec<String>>,
+}
+
+impl ScriptResolver {
+    pub fn new(uid: Option<HyUuid>) -> Self {
+        Self {
+            uid,
+            ..Default::default()
+        }
+    }
+}
+
+impl ModuleResolver for ScriptResolver {
+    fn resolve(
+        &self,
+        engine: &Engine,
+        _: Option<&str>,
+        path: &str,
+        pos: Position,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        if let Some(x) = self.cache.read().unwrap().get(path) {
+            return Ok(x.clone());
+        }
+        if self.loading.read().unwrap().iter().any(|x| x == path) {
+            return Err(EvalAltResult::ErrorInModule(
+                path.to_owned(),
+                format!(
+                    "Cyclic import: {} -> {path}",
+                    self.loading.read().unwrap().join(" -> ")
+                )
+                .into(),
+                pos,
+            )
+            .into());
+        }
+        let (script, allowed) = runtime::Handle::current()
+            .block_on(async {
+                let db = PLUGIN_INSTANCE.db.get().unwrap();
+                let script = ScriptViewer::find_library(db, path)
+                    .await?
+                    .filter(|x| x.runnable());
+                let allowed = match (&script, self.uid) {
+                    (Some(x), Some(uid)) => acl::check(db, &x.id, Some(uid), ACL_RUN).await?,
+                    _ => true,
+                };
+                Ok::<_, skynet_api::anyhow::Error>((script, allowed))
+            })
+            .map_err(|e| e.to_string())?;
+        let script =
+            script.ok_or_else(|| EvalAltResult::ErrorModuleNotFound(path.to_owned(), pos))?;
+        if !allowed {
+            return Err(EvalAltResult::ErrorInModule(
+                path.to_owned(),
+                "Permission denied by script access control".into(),
+                pos,
+            )
+            .into());
+        }
+        let ast = engine
+            .compile(&script.code)
+            .map_err(|e| EvalAltResult::ErrorInModule(path.to_owned(), e.into(), pos))?;
+
+        self.loading.write().unwrap().push(path.to_owned());
+        let module = Module::eval_ast_as_new(Scope::new(), &ast, engine);
+        self.loading.write().unwrap().pop();
+        let module: Shared<Module> = module
+            .map_err(|e| EvalAltResult::ErrorInModule(path.to_owned(), e, pos))?
+            .into();
+        self.cache
+            .write()
+            .unwrap()
+            .insert(path.to_owned(), module.clone());
+        Ok(module)
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use actix_cloud::{
+    chrono::Utc,
+    tokio::{runtime, task::JoinHandle, time},
+    tracing::{error, info},
+};
+use serde::{Deserialize, Serialize};
+use skynet_api::{
+    Result,
+    sea_orm::{ColumnTrait, Condition, ConnectionTrait},
+};
+use skynet_api_task::{
+    entity::tasks,
+    viewer::{settings::SettingViewer, tasks::TaskViewer},
+};
+use validator::Validate;
+
+use crate::{PLUGIN_INSTANCE, artifact};
+
+const SETTING_NAME: &str = "retention";
+const INTERVAL: Duration = Duration::from_secs(3600);
+const DAY: i64 = 24 * 3600 * 1000;
+
+/// Retention policy of completed tasks, unset fields keep tasks forever.
+#[derive(Debug, Default, Clone, Validate, Serialize, Deserialize)]
+pub struct Retention {
+    /// Delete completed tasks older than `keep_days` days.
+    #[validate(range(min = 1))]
+    pub keep_days: Option<u32>,
+    /// Delete failed tasks older than `keep_failed_days` days instead of `keep_days`.
+    #[validate(range(min = 1))]
+    pub keep_failed_days: Option<u32>,
+    /// Keep at most `keep_last` completed tasks of each script.
+    #[validate(range(min = 1))]
+    pub keep_last: Option<u32>,
+}
+
+impl Retention {
+    pub async fn get<C>(db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(SettingViewer::get(db, SETTING_NAME)
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn set<C>(&self, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        SettingViewer::set(db, SETTING_NAME, self).await
+    }
+
+    /// Delete expired tasks, return deleted rows.
+    pub async fn apply<C>(&self, db: &C) -> Result<u64>
+    where
+        C: ConnectionTrait,
+    {
+        let now = Utc::now().timestamp_millis();
+        let mut rows = 0;
+        if let Some(x) = self.keep_days {
+            let mut cond =
+                Condition::all().add(tasks::Column::UpdatedAt.lt(now - i64::from(x) * DAY));
+            if self.keep_failed_days.is_some() {
+                cond = cond.add(tasks::Column::Result.eq(0));
+            }
+            rows += TaskViewer::delete_completed_by(db, cond).await?;
+        }
+        if let Some(x) = self.keep_failed_days {
+            rows += TaskViewer::delete_completed_by(
+                db,
+                Condition::all()
+                    .add(tasks::Column::Result.ne(0))
+                    .add(tasks::Column::UpdatedAt.lt(now - i64::from(x) * DAY)),
+            )
+            .await?;
+        }
+        if let Some(x) = self.keep_last {
+            for sid in TaskViewer::find_sids(db).await? {
+                let id: Vec<_> = TaskViewer::find_completed_except(db, &sid, x.into())
+                    .await?
+                    .into_iter()
+                    .filter(|t| {
+                        self.keep_failed_days.is_none_or(|d| {
+                            t.result == Some(0) || t.updated_at < now - i64::from(d) * DAY
+                        })
+                    })
+                    .map(|t| t.id)
+                    .collect();
+                if !id.is_empty() {
+                    rows += TaskViewer::delete(db, &id).await?;
+                }
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// Start the background job applying the retention policy.
+pub fn start() -> JoinHandle<()> {
+    runtime::Handle::current().spawn(async {
+        loop {
+            let db = PLUGIN_INSTANCE.db.get().unwrap();
+            let ret = async {
+                let rows = Retention::get(db).await?.apply(db).await?;
+                if rows != 0 {
+                    artifact::clean().await?;
+                }
+                Ok::<_, skynet_api::anyhow::Error>(rows)
+            }
+            .await;
+            match ret {
+                Ok(0) => {}
+                Ok(rows) => info!(success = true, rows, "Apply task retention"),
+                Err(e) => error!(success = false, error = %e, "Apply task retention"),
+            }
+            time::sleep(INTERVAL).await;
+        }
+    })
+}
@@ -0,0 +1,135 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::RwLock,
+};
+
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use skynet_api::{Result, anyhow, bail};
+
+const NONCE_SIZE: usize = 12;
+const REDACTED: &str = "******";
+
+/// Encrypt and decrypt secret values with the key stored on the runtime path.
+pub struct SecretCipher(Aes256Gcm);
+
+impl SecretCipher {
+    /// Load the key from `path`, generate a new one if it does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        let key = if path.exists() {
+            let key = fs::read(path)?;
+            if key.len() != 32 {
+                bail!("Invalid secret key file {}", path.display());
+            }
+            *Key::<Aes256Gcm>::from_slice(&key)
+        } else {
+            let key = Aes256Gcm::generate_key(OsRng);
+            if let Some(x) = path.parent() {
+                fs::create_dir_all(x)?;
+            }
+            let mut opt = OpenOptions::new();
+            opt.write(true).create_new(true);
+            // Only the owner can read the key.
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut opt, 0o600);
+            opt.open(path)?.write_all(&key)?;
+            key
+        };
+        Ok(Self(Aes256Gcm::new(&key)))
+    }
+
+    pub fn encrypt(&self, value: &str) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let mut ret = nonce.to_vec();
+        ret.extend(
+            self.0
+                .encrypt(&nonce, value.as_bytes())
+                .map_err(|e| anyhow::anyhow!("Encrypt secret failed: {e}"))?,
+        );
+        Ok(hex::encode(ret))
+    }
+
+    pub fn decrypt(&self, value: &str) -> Result<String> {
+        let value = hex::decode(value)?;
+        if value.len() < NONCE_SIZE {
+            bail!("Invalid secret value");
+        }
+        let (nonce, data) = value.split_at(NONCE_SIZE);
+        let ret = self
+            .0
+            .decrypt(Nonce::from_slice(nonce), data)
+            .map_err(|e| anyhow::anyhow!("Decrypt secret failed: {e}"))?;
+        Ok(String::from_utf8(ret)?)
+    }
+}
+
+/// Hide secret values read by a script from its task output.
+#[derive(Default)]
+pub struct Redactor(RwLock<Vec<String>>);
+
+impl Redactor {
+    pub fn add(&self, value: &str) {
+        let mut x = self.0.write().unwrap();
+        if !value.is_empty() && !x.iter().any(|x| x == value) {
+            x.push(value.to_owned());
+            // Replace longer values first in case one secret contains another.
+            x.sort_by_key(|x| std::cmp::Reverse(x.len()));
+        }
+    }
+
+    pub fn redact(&self, output: &str) -> String {
+        let mut ret = output.to_owned();
+        for x in self.0.read().unwrap().iter() {
+            ret = ret.replace(x, REDACTED);
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_key() {
+        let path = std::env::temp_dir().join(format!("task-secret-{}/key", std::process::id()));
+        let cipher = SecretCipher::load(&path).unwrap();
+        let value = cipher.encrypt("abc").unwrap();
+        let ret = SecretCipher::load(&path).map(|x| x.decrypt(&value));
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            fs::metadata(&path).unwrap().permissions().mode()
+        };
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(ret.unwrap().unwrap(), "abc");
+        #[cfg(unix)]
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn redact_values() {
+        let r = Redactor::default();
+        assert_eq!(r.redact("token abc"), "token abc");
+        r.add("abc");
+        r.add("");
+        assert_eq!(
+            r.redact("token abc, abc"),
+            format!("token {REDACTED}, {REDACTED}")
+        );
+    }
+
+    #[test]
+    fn redact_longer_first() {
+        let r = Redactor::default();
+        r.add("pass");
+        r.add("password");
+        r.add("pass");
+        assert_eq!(r.redact("password"), REDACTED);
+        assert_eq!(r.0.read().unwrap().len(), 2);
+    }
+}
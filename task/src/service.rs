@@ -1,26 +1,76 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    hash::Hash,
+    pin::pin,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
-use actix_cloud::tokio::runtime;
-use rhai::{Engine, EvalAltResult, Position};
+use actix_cloud::{
+    chrono::Utc,
+    tokio::{runtime, time},
+    tracing::error,
+};
+use rhai::{Dynamic, Engine, EvalAltResult, ParseError, Position, Scope};
 use skynet_api::{
     HyUuid, Result, anyhow, bail,
     ffi_rpc::{self, async_trait, ffi_rpc_macro::plugin_impl_trait, registry::Registry, rmp_serde},
-    sea_orm::TransactionTrait,
+    sea_orm::{DbErr, SqlErr, TransactionTrait, prelude::Uuid},
     service::SResult,
 };
 use skynet_api_task::{
-    TaskCallback, TaskScript, Value,
+    EventFilter, Script, Service, TaskCallback, TaskEventKind, TaskScript, Value,
+    entity::{
+        scripts::Concurrency,
+        tasks::{self, Approval},
+    },
     semver::Version,
-    viewer::{scripts::ScriptViewer, tasks::TaskViewer},
+    viewer::{
+        scripts::ScriptViewer,
+        secrets::SecretViewer,
+        states::StateViewer,
+        tasks::{TaskRun, TaskViewer},
+    },
 };
 
-use crate::{PLUGIN_INSTANCE, Plugin};
+use crate::{
+    PLUGIN_INSTANCE, Plugin, artifact, events,
+    lock::{LockGuard, LockHandle},
+    logging::{LogLevel, format_record},
+    notify,
+    resolver::ScriptResolver,
+    secret::Redactor,
+    stub::StubRegistry,
+};
+
+/// Interval to check whether a task waiting for a lock is aborted.
+const LOCK_POLL: Duration = Duration::from_secs(1);
+
+/// Repeated creation with the same idempotency key within the window returns the existing task.
+const KEY_WINDOW: i64 = 24 * 3600 * 1000;
+
+/// Outcome of [`Plugin::approve`].
+pub enum Approve {
+    Started,
+    /// The script skips concurrent runs, the task is kept pending.
+    Running(HyUuid),
+    /// The task was decided or stopped concurrently.
+    NotPending,
+}
 
 impl Plugin {
     fn is_script_aborted(&self, id: &HyUuid) -> bool {
         self.script_handle.get(id).is_some_and(|x| *x)
     }
 
+    fn check_script_aborted(id: &HyUuid) -> Result<(), Box<EvalAltResult>> {
+        if PLUGIN_INSTANCE.is_script_aborted(id) {
+            Err(EvalAltResult::ErrorTerminated("Aborted".into(), Position::NONE).into())
+        } else {
+            Ok(())
+        }
+    }
+
     fn script_abort(&self, id: &HyUuid) -> bool {
         if let Some(mut x) = self.script_handle.get_mut(id) {
             *x = true;
@@ -30,147 +80,581 @@ impl Plugin {
         }
     }
 
-    fn param_script(p: BTreeMap<String, Value>) -> rhai::Map {
-        let mut ret = rhai::Map::new();
-        for (k, v) in p {
-            ret.insert(
-                k.into(),
-                match v {
-                    Value::String(x) => x.into(),
-                    Value::Integer(x) => x.into(),
-                    Value::Float(x) => x.into(),
-                    Value::Bool(x) => x.into(),
-                },
-            );
+    /// Find the task created by `plugin` with idempotency `key` within [`KEY_WINDOW`].
+    /// Keys of older tasks are released for reuse.
+    async fn find_key(plugin: Option<HyUuid>, key: &Option<String>) -> Result<Option<HyUuid>> {
+        let Some(key) = key else {
+            return Ok(None);
+        };
+        if key.len() > 64 {
+            bail!("Idempotency key `{key}` is longer than 64 bytes");
+        }
+        let db = PLUGIN_INSTANCE.db.get().unwrap();
+        match TaskViewer::find_by_key(db, plugin, key).await? {
+            Some(x) if x.created_at >= Utc::now().timestamp_millis() - KEY_WINDOW => Ok(Some(x.id)),
+            Some(x) => {
+                TaskViewer::clear_key(db, &x.id).await?;
+                Ok(None)
+            }
+            None => Ok(None),
         }
-        ret
     }
 
-    fn param_plugin(p: &rhai::Map) -> Result<BTreeMap<String, Value>> {
-        let mut ret = BTreeMap::new();
-        for (k, v) in p {
-            let v = if v.is::<i64>() {
-                Value::Integer(v.as_int().unwrap())
-            } else if v.is::<String>() {
-                Value::String(v.to_owned().into_string().unwrap())
-            } else if v.is::<f64>() {
-                Value::Float(v.as_float().unwrap())
-            } else if v.is::<bool>() {
-                Value::Bool(v.as_bool().unwrap())
-            } else {
-                bail!("Invalid param type {}", v.type_name());
-            };
-            ret.insert(k.to_string(), v);
+    /// Whether `e` is caused by a concurrent creation with the same idempotency key.
+    fn key_conflict(e: &anyhow::Error) -> bool {
+        e.downcast_ref::<DbErr>()
+            .and_then(|x| x.sql_err())
+            .is_some_and(|x| matches!(x, SqlErr::UniqueConstraintViolation(_)))
+    }
+
+    /// Append `output` to task `id` and add `percent` to its progress.
+    fn task_output(id: &HyUuid, output: &str, percent: u32) -> Result<()> {
+        runtime::Handle::current().block_on(async {
+            let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+            TaskViewer::update(&tx, id, output, percent).await?;
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn artifact_write(
+        id: &HyUuid,
+        stub: Option<&StubRegistry>,
+        redactor: &Redactor,
+        name: &str,
+        content: &[u8],
+    ) -> Result<(), Box<EvalAltResult>> {
+        if let Some(stub) = stub {
+            artifact::check(name, content).map_err(|x| x.to_string())?;
+            Self::dry_log(
+                id,
+                redactor,
+                &format!("artifact_write({name}, {} bytes)", content.len()),
+            )?;
+            stub.artifact_write(name, content);
+            return Ok(());
         }
-        Ok(ret)
+        runtime::Handle::current()
+            .block_on(artifact::write(id, name, content))
+            .map(|_| ())
+            .map_err(|x| x.to_string().into())
     }
-}
 
-#[plugin_impl_trait]
-impl skynet_api_task::Service for Plugin {
-    async fn api_version(&self, _: &Registry) -> Version {
-        Version::parse(skynet_api_task::VERSION).unwrap()
+    /// Log `call` of a dry-run task `id` handled by the stub.
+    fn dry_log(id: &HyUuid, redactor: &Redactor, call: &str) -> Result<(), Box<EvalAltResult>> {
+        let output = redactor.redact(&format_record(
+            LogLevel::Debug,
+            &format!("[dry-run] {call}"),
+        ));
+        Self::task_output(id, &output, 0).map_err(|x| x.to_string().into())
     }
 
-    async fn create(
+    /// Create a new script engine, shared by running and compiling scripts.
+    /// Libraries are imported with the access of user `uid`.
+    fn new_engine(uid: Option<HyUuid>) -> Engine {
+        let mut engine = Engine::new();
+        engine.set_module_resolver(ScriptResolver::new(uid));
+        engine
+    }
+
+    /// Compile `code` without running it.
+    pub fn compile_script(code: &str) -> Result<(), ParseError> {
+        Self::new_engine(None).compile(code).map(|_| ())
+    }
+
+    /// Wait for `lock` in task `id`, giving up when the task is aborted.
+    fn wait_lock<K: Eq + Hash>(
+        id: &HyUuid,
+        lock: LockHandle<K>,
+    ) -> Result<LockGuard<K>, Box<EvalAltResult>> {
+        runtime::Handle::current().block_on(async {
+            let mut fut = pin!(lock.lock());
+            loop {
+                if let Ok(x) = time::timeout(LOCK_POLL, &mut fut).await {
+                    return Ok(x);
+                }
+                Self::check_script_aborted(id)?;
+            }
+        })
+    }
+
+    /// Get the [`Concurrency`] policy of the script of `run` and its running tasks,
+    /// dry runs always run concurrently. Callers hold the start lock of the script.
+    async fn concurrency(run: &TaskRun) -> Result<(Concurrency, Vec<HyUuid>)> {
+        let db = PLUGIN_INSTANCE.db.get().unwrap();
+        let concurrency = match run.sid {
+            Some(sid) if !run.dry_run => ScriptViewer::find_by_id(db, &sid)
+                .await?
+                .map(|x| x.concurrency)
+                .unwrap_or_default(),
+            _ => Concurrency::Allow,
+        };
+        let running = match run.sid.filter(|_| concurrency != Concurrency::Allow) {
+            Some(sid) => TaskViewer::find_running_by_sid(db, &sid).await?,
+            None => Vec::new(),
+        };
+        Ok((concurrency, running))
+    }
+
+    /// Stop `running` tasks if the policy is [`Concurrency::Cancel`].
+    async fn cancel(&self, r: &Registry, concurrency: Concurrency, running: Vec<HyUuid>) {
+        if concurrency == Concurrency::Cancel {
+            for x in running {
+                self.stop(r, x).await;
+            }
+        }
+    }
+
+    /// Lock the start lock of the script of `run`, dry runs are not locked.
+    async fn start_lock(run: &TaskRun) -> Option<LockGuard<HyUuid>> {
+        match run.sid {
+            Some(sid) if !run.dry_run => {
+                Some(LockHandle::new(&PLUGIN_INSTANCE.start, sid).lock().await)
+            }
+            _ => None,
+        }
+    }
+
+    /// Create a task managed by the plugin of callback `cb`.
+    async fn create_task(
         &self,
-        _: &Registry,
+        r: &Registry,
         name: String,
         detail: Option<String>,
         cb: String,
-    ) -> SResult<HyUuid> {
-        let m = TaskViewer::create(PLUGIN_INSTANCE.db.get().unwrap(), &name, &detail).await?;
-        self.cb.insert(m.id, cb);
-        Ok(m.id)
-    }
-
-    async fn stop(&self, r: &Registry, id: HyUuid) -> bool {
-        let _ = TaskViewer::finish_out(
+        key: Option<String>,
+    ) -> Result<HyUuid> {
+        // Callbacks are registered by plugin ID, record it as the owner.
+        let plugin = cb.parse::<Uuid>().ok().map(HyUuid);
+        if key.is_some() && plugin.is_none() {
+            // Keys are unique per plugin, ownerless keys are never deduplicated.
+            bail!("Callback `{cb}` is not a plugin ID, idempotency keys are not supported");
+        }
+        if let Some(id) = Self::find_key(plugin, &key).await? {
+            return Ok(id);
+        }
+        let m = match TaskViewer::create(
             PLUGIN_INSTANCE.db.get().unwrap(),
-            &id,
-            9,
-            "Task aborted by the user",
+            &name,
+            &detail,
+            &key,
+            plugin,
         )
-        .await;
-        let x = self.cb.get(&id).map(|x| x.to_owned());
-        match x {
-            Some(x) => {
-                if x == "self" {
-                    self.script_abort(&id)
-                } else {
-                    match r.get(&x) {
-                        Some(x) => {
-                            let x: TaskCallback = x.into();
-                            x.stop(r, &id).await
-                        }
-                        None => false,
-                    }
-                }
+        .await
+        {
+            Ok(x) => x,
+            Err(e) if key.is_some() && Self::key_conflict(&e) => {
+                return Self::find_key(plugin, &key).await?.ok_or(e);
             }
-            None => false,
+            Err(e) => return Err(e),
+        };
+        self.cb.insert(m.id, cb);
+        if let Err(e) = events::emit(r, TaskEventKind::Created, &m.id).await {
+            error!(success = false, id = %m.id, error = %e, "Emit task event");
         }
+        Ok(m.id)
     }
 
-    async fn create_script(
+    /// Create a task running script `sid` for `plugin`, `None` if the script is not found.
+    async fn create_script_task(
         &self,
         r: &Registry,
         name: String,
         detail: Option<String>,
         sid: HyUuid,
-    ) -> SResult<Option<HyUuid>> {
+        plugin: Option<HyUuid>,
+        key: Option<String>,
+    ) -> Result<Option<HyUuid>> {
+        if let Some(id) = Self::find_key(plugin, &key).await? {
+            return Ok(Some(id));
+        }
         let s = ScriptViewer::find_by_id(PLUGIN_INSTANCE.db.get().unwrap(), &sid).await?;
         match s {
-            Some(s) => Ok(Some(self.create_code(r, name, detail, s.code).await?)),
+            Some(s) => {
+                if s.library {
+                    bail!("Library script `{}` cannot be run", s.name);
+                }
+                if !s.runnable() {
+                    bail!("Script `{}` is disabled", s.name);
+                }
+                let run = TaskRun {
+                    sid: Some(s.id),
+                    code: s.code,
+                    plugin,
+                    key,
+                    ..Default::default()
+                };
+                Ok(Some(self.run_code(r, name, detail, run, None).await?))
+            }
             None => Ok(None),
         }
     }
 
-    async fn create_code(
+    /// Create a task running `code` for `plugin`.
+    async fn create_code_task(
         &self,
         r: &Registry,
         name: String,
         detail: Option<String>,
         code: String,
-    ) -> SResult<HyUuid> {
+        plugin: Option<HyUuid>,
+        key: Option<String>,
+    ) -> Result<HyUuid> {
+        if let Some(id) = Self::find_key(plugin, &key).await? {
+            return Ok(id);
+        }
+        let run = TaskRun {
+            code,
+            plugin,
+            key,
+            ..Default::default()
+        };
+        self.run_code(r, name, detail, run, None).await
+    }
+
+    /// Run `run` in a new task. Dry runs answer `api_call` with `stub` instead of the
+    /// real plugins, or with empty responses if `stub` is not set.
+    ///
+    /// Non dry-run tasks of a script follow its [`Concurrency`] policy, a skipped run
+    /// returns the running task. The policy is applied and the task created under the
+    /// start lock of the script, so that concurrent runs see each other. Tasks of scripts requiring approval are created
+    /// pending and run by [`Self::approve`].
+    pub async fn run_code(
+        &self,
+        r: &Registry,
+        name: String,
+        detail: Option<String>,
+        run: TaskRun,
+        stub: Option<StubRegistry>,
+    ) -> Result<HyUuid> {
+        let db = PLUGIN_INSTANCE.db.get().unwrap();
+        let _guard = Self::start_lock(&run).await;
+        let pending = match run.sid {
+            Some(sid) if !run.dry_run => ScriptViewer::find_by_id(db, &sid)
+                .await?
+                .is_some_and(|x| x.approval),
+            _ => false,
+        };
+        let (concurrency, running) = if pending {
+            (Concurrency::Allow, Vec::new())
+        } else {
+            Self::concurrency(&run).await?
+        };
+        if concurrency == Concurrency::Skip
+            && let Some(x) = running.first()
+        {
+            return Ok(*x);
+        }
+        self.cancel(r, concurrency, running).await;
+        let id = match TaskViewer::create_run(db, &name, &detail, &run, pending).await {
+            Ok(x) => x.id,
+            Err(e) if run.key.is_some() && Self::key_conflict(&e) => {
+                return Self::find_key(run.plugin, &run.key).await?.ok_or(e);
+            }
+            Err(e) => return Err(e),
+        };
+        if let Err(e) = events::emit(r, TaskEventKind::Created, &id).await {
+            error!(success = false, id = %id, error = %e, "Emit task event");
+        }
+        if !pending {
+            self.exec(r, id, run, stub, concurrency);
+        }
+        Ok(id)
+    }
+
+    /// Approve pending task `t` by user `uid` and run it.
+    pub async fn approve(
+        &self,
+        r: &Registry,
+        t: tasks::Model,
+        uid: Option<HyUuid>,
+        reason: &Option<String>,
+    ) -> Result<Approve> {
+        let params = TaskViewer::params(&t)?.unwrap_or_default();
+        let run = TaskRun {
+            sid: t.sid,
+            code: t.code.unwrap_or_default(),
+            params,
+            parent: t.parent,
+            uid: t.uid,
+            dry_run: t.dry_run,
+            plugin: t.plugin,
+            key: None,
+        };
+        let _guard = Self::start_lock(&run).await;
+        let (concurrency, running) = Self::concurrency(&run).await?;
+        if concurrency == Concurrency::Skip
+            && let Some(x) = running.first()
+        {
+            return Ok(Approve::Running(*x));
+        }
+        // Decide first, running tasks are only cancelled for a task still pending.
+        if !TaskViewer::decide(
+            PLUGIN_INSTANCE.db.get().unwrap(),
+            &t.id,
+            Approval::Approved,
+            uid,
+            reason,
+        )
+        .await?
+        {
+            return Ok(Approve::NotPending);
+        }
+        self.cancel(r, concurrency, running).await;
+        self.exec(r, t.id, run, None, concurrency);
+        Ok(Approve::Started)
+    }
+
+    /// Run `run` in created task `id` in the background.
+    fn exec(
+        &self,
+        r: &Registry,
+        id: HyUuid,
+        run: TaskRun,
+        stub: Option<StubRegistry>,
+        concurrency: Concurrency,
+    ) {
         let r = r.clone();
-        let id = self.create(&r, name, detail, String::from("self")).await?;
+        let TaskRun {
+            sid,
+            code,
+            params,
+            uid,
+            dry_run,
+            ..
+        } = run;
+        let stub = dry_run.then(|| stub.unwrap_or_default());
+        self.cb.insert(id, String::from("self"));
         self.script_handle.insert(id, false);
         runtime::Handle::current().spawn_blocking(move || {
-            let mut engine = Engine::new();
+            let stub = stub.map(Arc::new);
+            let redactor = Arc::new(Redactor::default());
+            let locks: Arc<RwLock<BTreeMap<String, LockGuard<String>>>> = Default::default();
+            let mut engine = Self::new_engine(uid);
+            let _locks = locks.clone();
+            let _stub = stub.clone();
+            let _redactor = redactor.clone();
+            engine.register_fn(
+                "lock_acquire",
+                move |name: &str| -> Result<bool, Box<EvalAltResult>> {
+                    if let Some(stub) = &_stub {
+                        Self::dry_log(&id, &_redactor, &format!("lock_acquire({name})"))?;
+                        return Ok(stub.lock_acquire(name));
+                    }
+                    if _locks.read().unwrap().contains_key(name) {
+                        return Ok(false);
+                    }
+                    let lock = LockHandle::new(&PLUGIN_INSTANCE.locks, name.to_owned());
+                    let guard = Self::wait_lock(&id, lock)?;
+                    _locks.write().unwrap().insert(name.to_owned(), guard);
+                    Ok(true)
+                },
+            );
+            let _locks = locks.clone();
+            let _stub = stub.clone();
+            let _redactor = redactor.clone();
+            engine.register_fn(
+                "lock_release",
+                move |name: &str| -> Result<bool, Box<EvalAltResult>> {
+                    if let Some(stub) = &_stub {
+                        Self::dry_log(&id, &_redactor, &format!("lock_release({name})"))?;
+                        return Ok(stub.lock_release(name));
+                    }
+                    Ok(_locks.write().unwrap().remove(name).is_some())
+                },
+            );
+            let _redactor = redactor.clone();
+            let _r = r.clone();
             engine.register_fn(
                 "task_update",
                 move |output: &str, percent: i64| -> Result<(), Box<EvalAltResult>> {
-                    if PLUGIN_INSTANCE.is_script_aborted(&id) {
-                        return Err(EvalAltResult::ErrorTerminated(
-                            "Aborted".into(),
-                            Position::NONE,
-                        )
-                        .into());
+                    Self::check_script_aborted(&id)?;
+                    Self::task_output(&id, &_redactor.redact(output), percent as u32)
+                        .map_err(|x| x.to_string())?;
+                    if let Err(e) = runtime::Handle::current().block_on(events::emit(
+                        &_r,
+                        TaskEventKind::Progress,
+                        &id,
+                    )) {
+                        error!(success = false, id = %id, error = %e, "Emit task event");
                     }
-                    let output = output.to_owned();
-                    runtime::Handle::current()
+                    Ok(())
+                },
+            );
+            for (name, level) in [
+                ("log_info", LogLevel::Info),
+                ("log_warn", LogLevel::Warn),
+                ("log_error", LogLevel::Error),
+            ] {
+                let _redactor = redactor.clone();
+                engine.register_fn(
+                    name,
+                    move |message: &str| -> Result<(), Box<EvalAltResult>> {
+                        Self::check_script_aborted(&id)?;
+                        let output = _redactor.redact(&format_record(level, message));
+                        Self::task_output(&id, &output, 0).map_err(|x| x.to_string().into())
+                    },
+                );
+            }
+            let _redactor = redactor.clone();
+            engine.on_print(move |x| {
+                let _ =
+                    Self::task_output(&id, &_redactor.redact(&format_record(LogLevel::Info, x)), 0);
+            });
+            let _redactor = redactor.clone();
+            engine.on_debug(move |x, _, pos| {
+                let message = if pos.is_none() {
+                    x.to_owned()
+                } else {
+                    format!("{x} ({pos})")
+                };
+                let _ = Self::task_output(
+                    &id,
+                    &_redactor.redact(&format_record(LogLevel::Debug, &message)),
+                    0,
+                );
+            });
+            let _redactor = redactor.clone();
+            engine.register_fn(
+                "secret",
+                move |name: &str| -> Result<String, Box<EvalAltResult>> {
+                    Self::check_script_aborted(&id)?;
+                    let value = runtime::Handle::current()
                         .block_on(async {
-                            let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
-                            TaskViewer::update(&tx, &id, &output, percent as u32).await?;
-                            tx.commit().await?;
-                            Ok(())
+                            match SecretViewer::find_by_name(
+                                PLUGIN_INSTANCE.db.get().unwrap(),
+                                name,
+                            )
+                            .await?
+                            {
+                                Some(x) => PLUGIN_INSTANCE
+                                    .secret
+                                    .get()
+                                    .unwrap()
+                                    .decrypt(&x.value)
+                                    .map(Some),
+                                None => Ok(None),
+                            }
                         })
-                        .map_err(|x: anyhow::Error| x.to_string().into())
+                        .map_err(|x: anyhow::Error| x.to_string())?
+                        .ok_or_else(|| format!("Secret `{name}` not exist"))?;
+                    _redactor.add(&value);
+                    Ok(value)
+                },
+            );
+            let _stub = stub.clone();
+            let _redactor = redactor.clone();
+            engine.register_fn(
+                "artifact_write",
+                move |name: &str, content: &str| -> Result<(), Box<EvalAltResult>> {
+                    Self::check_script_aborted(&id)?;
+                    Self::artifact_write(
+                        &id,
+                        _stub.as_deref(),
+                        &_redactor,
+                        name,
+                        content.as_bytes(),
+                    )
+                },
+            );
+            let _stub = stub.clone();
+            let _redactor = redactor.clone();
+            engine.register_fn(
+                "artifact_write",
+                move |name: &str, content: rhai::Blob| -> Result<(), Box<EvalAltResult>> {
+                    Self::check_script_aborted(&id)?;
+                    Self::artifact_write(&id, _stub.as_deref(), &_redactor, name, &content)
+                },
+            );
+            let _stub = stub.clone();
+            engine.register_fn(
+                "state_get",
+                move |key: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+                    Self::check_script_aborted(&id)?;
+                    let value = match &_stub {
+                        Some(stub) => stub.state.read().unwrap().get(key).cloned(),
+                        None => {
+                            let sid = sid.ok_or("State is only available in saved scripts")?;
+                            runtime::Handle::current()
+                                .block_on(StateViewer::get(
+                                    PLUGIN_INSTANCE.db.get().unwrap(),
+                                    &sid,
+                                    key,
+                                ))
+                                .map_err(|x| x.to_string())?
+                        }
+                    };
+                    Ok(value.map_or(Dynamic::UNIT, Self::value_script))
+                },
+            );
+            let _stub = stub.clone();
+            engine.register_fn(
+                "state_set",
+                move |key: &str, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+                    Self::check_script_aborted(&id)?;
+                    let value = Self::value_plugin(&value).map_err(|x| x.to_string())?;
+                    match &_stub {
+                        Some(stub) => {
+                            stub.state.write().unwrap().insert(key.to_owned(), value);
+                        }
+                        None => {
+                            let sid = sid.ok_or("State is only available in saved scripts")?;
+                            runtime::Handle::current()
+                                .block_on(StateViewer::set(
+                                    PLUGIN_INSTANCE.db.get().unwrap(),
+                                    &sid,
+                                    key,
+                                    &value,
+                                ))
+                                .map_err(|x| x.to_string())?;
+                        }
+                    }
+                    Ok(())
+                },
+            );
+            let _stub = stub.clone();
+            engine.register_fn(
+                "state_delete",
+                move |key: &str| -> Result<bool, Box<EvalAltResult>> {
+                    Self::check_script_aborted(&id)?;
+                    match &_stub {
+                        Some(stub) => Ok(stub.state.write().unwrap().remove(key).is_some()),
+                        None => {
+                            let sid = sid.ok_or("State is only available in saved scripts")?;
+                            runtime::Handle::current()
+                                .block_on(StateViewer::delete(
+                                    PLUGIN_INSTANCE.db.get().unwrap(),
+                                    &sid,
+                                    key,
+                                ))
+                                .map(|x| x != 0)
+                                .map_err(|x| x.to_string().into())
+                        }
+                    }
                 },
             );
             let _r = r.clone();
+            let _redactor = redactor.clone();
             engine.register_fn(
                 "api_call",
                 move |pid: &str,
                       name: &str,
                       param: rhai::Map|
                       -> Result<rhai::Map, Box<EvalAltResult>> {
-                    if PLUGIN_INSTANCE.is_script_aborted(&id) {
-                        return Err(EvalAltResult::ErrorTerminated(
-                            "Aborted".into(),
-                            Position::NONE,
-                        )
-                        .into());
+                    Self::check_script_aborted(&id)?;
+                    if let Some(stub) = &stub {
+                        let param = Self::param_plugin(&param).map_err(|x| x.to_string())?;
+                        Self::dry_log(
+                            &id,
+                            &_redactor,
+                            &format!("api_call({pid}, {name}, {param:?})"),
+                        )?;
+                        return runtime::Handle::current()
+                            .block_on(async {
+                                let ret = stub.get(pid).call(&_r, name.to_owned(), param).await?;
+                                Ok(Self::param_script(ret))
+                            })
+                            .map_err(|x: anyhow::Error| x.to_string().into());
                     }
                     if let Some(x) = _r.get(pid) {
                         runtime::Handle::current()
@@ -186,7 +670,16 @@ impl skynet_api_task::Service for Plugin {
                     }
                 },
             );
-            let ret = engine.eval::<i64>(&code);
+            let mut scope = Scope::new();
+            scope.push_constant("param", Self::param_script(params));
+            let queue = match sid.filter(|_| concurrency == Concurrency::Queue) {
+                Some(sid) => {
+                    Self::wait_lock(&id, LockHandle::new(&PLUGIN_INSTANCE.queue, sid)).map(Some)
+                }
+                None => Ok(None),
+            };
+            let ret = queue.and_then(|_guard| engine.eval_with_scope::<i64>(&mut scope, &code));
+            locks.write().unwrap().clear();
             if !PLUGIN_INSTANCE.is_script_aborted(&id) {
                 runtime::Handle::current().block_on(async {
                     match ret {
@@ -203,15 +696,255 @@ impl skynet_api_task::Service for Plugin {
                                 PLUGIN_INSTANCE.db.get().unwrap(),
                                 &id,
                                 1,
-                                &e.to_string(),
+                                &redactor.redact(&format_record(LogLevel::Error, &e.to_string())),
                             )
                             .await;
                         }
                     }
+                    if let Err(e) = events::emit(&r, TaskEventKind::Finished, &id).await {
+                        error!(success = false, id = %id, error = %e, "Emit task event");
+                    }
+                    if let Err(e) = notify::finish(&r, &id).await {
+                        error!(success = false, id = %id, error = %e, "Notify task");
+                    }
                 });
             }
             PLUGIN_INSTANCE.script_handle.remove(&id);
         });
-        Ok(id)
+    }
+
+    fn value_script(v: Value) -> Dynamic {
+        match v {
+            Value::String(x) => x.into(),
+            Value::Integer(x) => x.into(),
+            Value::Float(x) => x.into(),
+            Value::Bool(x) => x.into(),
+        }
+    }
+
+    fn value_plugin(v: &Dynamic) -> Result<Value> {
+        Ok(if v.is::<i64>() {
+            Value::Integer(v.as_int().unwrap())
+        } else if v.is::<String>() {
+            Value::String(v.to_owned().into_string().unwrap())
+        } else if v.is::<f64>() {
+            Value::Float(v.as_float().unwrap())
+        } else if v.is::<bool>() {
+            Value::Bool(v.as_bool().unwrap())
+        } else {
+            bail!("Invalid param type {}", v.type_name());
+        })
+    }
+
+    fn param_script(p: BTreeMap<String, Value>) -> rhai::Map {
+        let mut ret = rhai::Map::new();
+        for (k, v) in p {
+            ret.insert(k.into(), Self::value_script(v));
+        }
+        ret
+    }
+
+    fn param_plugin(p: &rhai::Map) -> Result<BTreeMap<String, Value>> {
+        let mut ret = BTreeMap::new();
+        for (k, v) in p {
+            ret.insert(k.to_string(), Self::value_plugin(v)?);
+        }
+        Ok(ret)
+    }
+}
+
+#[plugin_impl_trait]
+impl skynet_api_task::Service for Plugin {
+    async fn api_version(&self, _: &Registry) -> Version {
+        Version::parse(skynet_api_task::VERSION).unwrap()
+    }
+
+    async fn create(
+        &self,
+        r: &Registry,
+        name: String,
+        detail: Option<String>,
+        cb: String,
+    ) -> SResult<HyUuid> {
+        Ok(self.create_task(r, name, detail, cb, None).await?)
+    }
+
+    async fn create_with_key(
+        &self,
+        r: &Registry,
+        name: String,
+        detail: Option<String>,
+        cb: String,
+        key: String,
+    ) -> SResult<HyUuid> {
+        Ok(self.create_task(r, name, detail, cb, Some(key)).await?)
+    }
+
+    async fn stop(&self, r: &Registry, id: HyUuid) -> bool {
+        let db = PLUGIN_INSTANCE.db.get().unwrap();
+        let (running, pending) = match TaskViewer::find_by_id(db, &id).await.ok().flatten() {
+            Some(x) if x.result.is_none() => (true, x.approval == Some(Approval::Pending)),
+            _ => (false, false),
+        };
+        if running {
+            let _ = TaskViewer::finish_out(db, &id, 9, "Task aborted by the user").await;
+            if let Err(e) = events::emit(r, TaskEventKind::Stopped, &id).await {
+                error!(success = false, id = %id, error = %e, "Emit task event");
+            }
+            if let Err(e) = notify::finish(r, &id).await {
+                error!(success = false, id = %id, error = %e, "Notify task");
+            }
+        }
+        // Pending tasks are not started, finishing them is enough.
+        if pending {
+            return true;
+        }
+        let x = self.cb.get(&id).map(|x| x.to_owned());
+        match x {
+            Some(x) => {
+                if x == "self" {
+                    self.script_abort(&id)
+                } else {
+                    match r.get(&x) {
+                        Some(x) => {
+                            let x: TaskCallback = x.into();
+                            x.stop(r, &id).await
+                        }
+                        None => false,
+                    }
+                }
+            }
+            None => false,
+        }
+    }
+
+    async fn create_script(
+        &self,
+        r: &Registry,
+        name: String,
+        detail: Option<String>,
+        sid: HyUuid,
+    ) -> SResult<Option<HyUuid>> {
+        Ok(self
+            .create_script_task(r, name, detail, sid, None, None)
+            .await?)
+    }
+
+    async fn create_script_by(
+        &self,
+        r: &Registry,
+        name: String,
+        detail: Option<String>,
+        sid: HyUuid,
+        plugin: HyUuid,
+    ) -> SResult<Option<HyUuid>> {
+        Ok(self
+            .create_script_task(r, name, detail, sid, Some(plugin), None)
+            .await?)
+    }
+
+    async fn create_script_with_key(
+        &self,
+        r: &Registry,
+        name: String,
+        detail: Option<String>,
+        sid: HyUuid,
+        plugin: HyUuid,
+        key: String,
+    ) -> SResult<Option<HyUuid>> {
+        Ok(self
+            .create_script_task(r, name, detail, sid, Some(plugin), Some(key))
+            .await?)
+    }
+
+    async fn create_code(
+        &self,
+        r: &Registry,
+        name: String,
+        detail: Option<String>,
+        code: String,
+    ) -> SResult<HyUuid> {
+        Ok(self
+            .create_code_task(r, name, detail, code, None, None)
+            .await?)
+    }
+
+    async fn create_code_by(
+        &self,
+        r: &Registry,
+        name: String,
+        detail: Option<String>,
+        code: String,
+        plugin: HyUuid,
+    ) -> SResult<HyUuid> {
+        Ok(self
+            .create_code_task(r, name, detail, code, Some(plugin), None)
+            .await?)
+    }
+
+    async fn create_code_with_key(
+        &self,
+        r: &Registry,
+        name: String,
+        detail: Option<String>,
+        code: String,
+        plugin: HyUuid,
+        key: String,
+    ) -> SResult<HyUuid> {
+        Ok(self
+            .create_code_task(r, name, detail, code, Some(plugin), Some(key))
+            .await?)
+    }
+
+    async fn attach(
+        &self,
+        _: &Registry,
+        id: HyUuid,
+        name: String,
+        content: Vec<u8>,
+    ) -> SResult<Option<HyUuid>> {
+        Ok(artifact::write(&id, &name, &content).await?.map(|x| x.id))
+    }
+
+    async fn subscribe(&self, _: &Registry, cb: String, filter: EventFilter) {
+        self.events.insert(cb, filter);
+    }
+
+    async fn unsubscribe(&self, _: &Registry, cb: String) -> bool {
+        self.events.remove(&cb).is_some()
+    }
+
+    async fn update(
+        &self,
+        r: &Registry,
+        id: HyUuid,
+        output: String,
+        percent: u32,
+    ) -> SResult<bool> {
+        let tx = PLUGIN_INSTANCE.db.get().unwrap().begin().await?;
+        if !TaskViewer::update(&tx, &id, &output, percent).await? {
+            return Ok(false);
+        }
+        tx.commit().await?;
+        if let Err(e) = events::emit(r, TaskEventKind::Progress, &id).await {
+            error!(success = false, id = %id, error = %e, "Emit task event");
+        }
+        Ok(true)
+    }
+
+    async fn finish(&self, r: &Registry, id: HyUuid, result: i32) -> SResult<bool> {
+        let db = PLUGIN_INSTANCE.db.get().unwrap();
+        match TaskViewer::find_by_id(db, &id).await? {
+            Some(x) if x.result.is_none() => {}
+            _ => return Ok(false),
+        }
+        TaskViewer::finish(db, &id, result).await?;
+        if let Err(e) = events::emit(r, TaskEventKind::Finished, &id).await {
+            error!(success = false, id = %id, error = %e, "Emit task event");
+        }
+        if let Err(e) = notify::finish(r, &id).await {
+            error!(success = false, id = %id, error = %e, "Notify task");
+        }
+        Ok(true)
     }
 }
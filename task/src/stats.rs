@@ -0,0 +1,282 @@
+use std::{cmp::Reverse, collections::HashMap, fmt::Write};
+
+use serde::Serialize;
+use skynet_api::{
+    HyUuid, Result,
+    sea_orm::{ColumnTrait, Condition, ConnectionTrait},
+};
+use skynet_api_task::{
+    entity::tasks::{self, Approval},
+    viewer::{scripts::ScriptViewer, settings::SettingViewer, tasks::TaskViewer},
+};
+
+use crate::PLUGIN_INSTANCE;
+
+const TOKEN_SETTING: &str = "metrics_token";
+
+/// Task statistics, durations are in milliseconds.
+#[derive(Debug, Default, Serialize)]
+pub struct Stats {
+    pub total: u64,
+    pub success: u64,
+    pub failed: u64,
+    /// Tasks running now, regardless of the time window.
+    pub running: u64,
+    pub success_rate: Option<f64>,
+    pub avg_duration: Option<f64>,
+    pub p95_duration: Option<i64>,
+    pub scripts: Vec<ScriptStats>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ScriptStats {
+    pub sid: HyUuid,
+    pub name: Option<String>,
+    pub total: u64,
+    pub success: u64,
+    pub failed: u64,
+    pub success_rate: Option<f64>,
+    pub avg_duration: Option<f64>,
+    pub p95_duration: Option<i64>,
+}
+
+#[derive(Default)]
+struct Counter {
+    total: u64,
+    success: u64,
+    failed: u64,
+    duration: Vec<i64>,
+}
+
+impl Counter {
+    fn add(&mut self, result: Option<i32>, duration: Option<i64>) {
+        self.total += 1;
+        match result {
+            Some(0) => self.success += 1,
+            Some(_) => self.failed += 1,
+            None => {}
+        }
+        if let Some(x) = duration {
+            self.duration.push(x);
+        }
+    }
+
+    fn success_rate(&self) -> Option<f64> {
+        let finished = self.success + self.failed;
+        (finished != 0).then(|| self.success as f64 / finished as f64)
+    }
+
+    fn avg_duration(&self) -> Option<f64> {
+        (!self.duration.is_empty())
+            .then(|| self.duration.iter().sum::<i64>() as f64 / self.duration.len() as f64)
+    }
+
+    fn p95_duration(&mut self) -> Option<i64> {
+        if self.duration.is_empty() {
+            return None;
+        }
+        self.duration.sort_unstable();
+        let idx = (self.duration.len() * 95).div_ceil(100) - 1;
+        Some(self.duration[idx])
+    }
+}
+
+impl Stats {
+    /// Compute statistics of tasks created between `start` and `end` among tasks matching
+    /// `visible`, all tasks if `None`.
+    pub async fn compute<C>(
+        db: &C,
+        start: Option<i64>,
+        end: Option<i64>,
+        visible: Option<Condition>,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let visible = visible.unwrap_or_else(Condition::all);
+        let mut cond = Condition::all().add(tasks::Column::DryRun.eq(false)).add(
+            Condition::any()
+                .add(tasks::Column::Approval.is_null())
+                .add(tasks::Column::Approval.ne(Approval::Pending)),
+        );
+        if let Some(x) = start {
+            cond = cond.add(tasks::Column::CreatedAt.gte(x));
+        }
+        if let Some(x) = end {
+            cond = cond.add(tasks::Column::CreatedAt.lte(x));
+        }
+        cond = cond.add(visible.clone());
+        let mut all = Counter::default();
+        let mut scripts: HashMap<HyUuid, Counter> = HashMap::new();
+        for (sid, result, started_at, finished_at) in TaskViewer::find_stats(db, cond).await? {
+            let duration = started_at.zip(finished_at).map(|(s, f)| f - s);
+            all.add(result, duration);
+            if let Some(sid) = sid {
+                scripts.entry(sid).or_default().add(result, duration);
+            }
+        }
+
+        let mut ret = Self {
+            total: all.total,
+            success: all.success,
+            failed: all.failed,
+            running: TaskViewer::count_running(db, visible).await?,
+            success_rate: all.success_rate(),
+            avg_duration: all.avg_duration(),
+            p95_duration: all.p95_duration(),
+            scripts: Vec::new(),
+        };
+        let sid: Vec<_> = scripts.keys().copied().collect();
+        let mut names = ScriptViewer::find_names(db, &sid).await?;
+        for (sid, mut x) in scripts {
+            ret.scripts.push(ScriptStats {
+                sid,
+                name: names.remove(&sid),
+                total: x.total,
+                success: x.success,
+                failed: x.failed,
+                success_rate: x.success_rate(),
+                avg_duration: x.avg_duration(),
+                p95_duration: x.p95_duration(),
+            });
+        }
+        ret.scripts.sort_by_key(|x| Reverse(x.total));
+        Ok(ret)
+    }
+
+    /// Set the bearer token of the scrape endpoint, `None` disables it.
+    /// The token is stored encrypted.
+    pub async fn set_token<C>(db: &C, token: Option<&str>) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let token = token
+            .map(|x| PLUGIN_INSTANCE.secret.get().unwrap().encrypt(x))
+            .transpose()?;
+        SettingViewer::set(db, TOKEN_SETTING, &token).await
+    }
+
+    /// Check the `Authorization: Bearer` value against the scrape token.
+    pub async fn check_token<C>(db: &C, auth: Option<&str>) -> Result<bool>
+    where
+        C: ConnectionTrait,
+    {
+        let Some(auth) = auth.and_then(|x| x.strip_prefix("Bearer ")) else {
+            return Ok(false);
+        };
+        let Some(token) = SettingViewer::get::<_, Option<String>>(db, TOKEN_SETTING)
+            .await?
+            .flatten()
+        else {
+            return Ok(false);
+        };
+        let token = PLUGIN_INSTANCE.secret.get().unwrap().decrypt(&token)?;
+        Ok(token.len() == auth.len()
+            && token
+                .bytes()
+                .zip(auth.bytes())
+                .fold(0, |acc, (a, b)| acc | (a ^ b))
+                == 0)
+    }
+
+    /// Format statistics in Prometheus text format.
+    pub fn prometheus(&self) -> String {
+        fn escape(s: &str) -> String {
+            s.replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+        }
+
+        let mut ret = String::new();
+        let _ = writeln!(ret, "# HELP skynet_task_tasks Number of tasks by status.");
+        let _ = writeln!(ret, "# TYPE skynet_task_tasks gauge");
+        for (status, x) in [
+            ("running", self.running),
+            ("success", self.success),
+            ("failed", self.failed),
+        ] {
+            let _ = writeln!(ret, "skynet_task_tasks{{status=\"{status}\"}} {x}");
+        }
+        let _ = writeln!(
+            ret,
+            "# HELP skynet_task_duration_seconds Duration of finished tasks."
+        );
+        let _ = writeln!(ret, "# TYPE skynet_task_duration_seconds gauge");
+        if let Some(x) = self.avg_duration {
+            let _ = writeln!(
+                ret,
+                "skynet_task_duration_seconds{{stat=\"avg\"}} {}",
+                x / 1000.0
+            );
+        }
+        if let Some(x) = self.p95_duration {
+            let _ = writeln!(
+                ret,
+                "skynet_task_duration_seconds{{stat=\"p95\"}} {}",
+                x as f64 / 1000.0
+            );
+        }
+        let _ = writeln!(
+            ret,
+            "# HELP skynet_task_script_tasks Number of finished tasks by script and status."
+        );
+        let _ = writeln!(ret, "# TYPE skynet_task_script_tasks gauge");
+        for x in &self.scripts {
+            let name = escape(x.name.as_deref().unwrap_or_default());
+            for (status, cnt) in [("success", x.success), ("failed", x.failed)] {
+                let _ = writeln!(
+                    ret,
+                    "skynet_task_script_tasks{{sid=\"{}\",name=\"{name}\",status=\"{status}\"}} {cnt}",
+                    x.sid
+                );
+            }
+        }
+        let _ = writeln!(
+            ret,
+            "# HELP skynet_task_script_duration_seconds Duration of finished tasks by script."
+        );
+        let _ = writeln!(ret, "# TYPE skynet_task_script_duration_seconds gauge");
+        for x in &self.scripts {
+            let name = escape(x.name.as_deref().unwrap_or_default());
+            if let Some(d) = x.avg_duration {
+                let _ = writeln!(
+                    ret,
+                    "skynet_task_script_duration_seconds{{sid=\"{}\",name=\"{name}\",stat=\"avg\"}} {}",
+                    x.sid,
+                    d / 1000.0
+                );
+            }
+            if let Some(d) = x.p95_duration {
+                let _ = writeln!(
+                    ret,
+                    "skynet_task_script_duration_seconds{{sid=\"{}\",name=\"{name}\",stat=\"p95\"}} {}",
+                    x.sid,
+                    d as f64 / 1000.0
+                );
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn p95_duration() {
+        let mut x = Counter::default();
+        assert_eq!(x.p95_duration(), None);
+        for i in (1..=100).rev() {
+            x.add(Some(0), Some(i));
+        }
+        assert_eq!(x.p95_duration(), Some(95));
+
+        let mut x = Counter::default();
+        x.add(Some(1), Some(7));
+        x.add(None, None);
+        assert_eq!(x.p95_duration(), Some(7));
+        assert_eq!(x.total, 2);
+        assert_eq!(x.failed, 1);
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::RwLock,
+};
+
+use skynet_api::{
+    ffi_rpc::{async_trait, registry::Registry},
+    service::SResult,
+};
+use skynet_api_task::{Script, Value};
+
+/// Stub of a plugin implementing [`Script`], answering calls with canned responses.
+#[derive(Default)]
+pub struct StubScript {
+    responses: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+#[async_trait::async_trait]
+impl Script for StubScript {
+    async fn call(
+        &self,
+        _: &Registry,
+        name: String,
+        _: BTreeMap<String, Value>,
+    ) -> SResult<BTreeMap<String, Value>> {
+        Ok(self.responses.get(&name).cloned().unwrap_or_default())
+    }
+}
+
+/// Stub registry used by dry-run tasks in place of plugins, locks, artifacts and script state,
+/// keeping everything in memory.
+#[derive(Default)]
+pub struct StubRegistry {
+    scripts: BTreeMap<String, StubScript>,
+    pub state: RwLock<BTreeMap<String, Value>>,
+    locks: RwLock<BTreeSet<String>>,
+    artifacts: RwLock<BTreeMap<String, Vec<u8>>>,
+}
+
+impl StubRegistry {
+    /// Answer calls to `name` of plugin `pid` with `response`.
+    pub fn insert(&mut self, pid: &str, name: &str, response: BTreeMap<String, Value>) {
+        self.scripts
+            .entry(pid.to_owned())
+            .or_default()
+            .responses
+            .insert(name.to_owned(), response);
+    }
+
+    /// Get the stub of plugin `pid`, plugins without canned responses answer empty.
+    pub fn get(&self, pid: &str) -> &StubScript {
+        static EMPTY: StubScript = StubScript {
+            responses: BTreeMap::new(),
+        };
+        self.scripts.get(pid).unwrap_or(&EMPTY)
+    }
+
+    /// Acquire lock `name`, `false` if already held. Locks are never shared with other tasks.
+    pub fn lock_acquire(&self, name: &str) -> bool {
+        self.locks.write().unwrap().insert(name.to_owned())
+    }
+
+    /// Release lock `name`, `false` if not held.
+    pub fn lock_release(&self, name: &str) -> bool {
+        self.locks.write().unwrap().remove(name)
+    }
+
+    /// Keep `content` as artifact `name`, replacing the previous one.
+    pub fn artifact_write(&self, name: &str, content: &[u8]) {
+        self.artifacts
+            .write()
+            .unwrap()
+            .insert(name.to_owned(), content.to_vec());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_cloud::tokio::runtime;
+
+    use super::*;
+
+    #[test]
+    fn stub_registry() {
+        let rt = runtime::Builder::new_current_thread().build().unwrap();
+        let r = Registry::default();
+        let mut stub = StubRegistry::default();
+        let response = BTreeMap::from([(String::from("ok"), Value::Bool(true))]);
+        stub.insert("p", "f", response.clone());
+        let call = |pid, name: &str| {
+            rt.block_on(stub.get(pid).call(&r, name.to_owned(), BTreeMap::new()))
+                .unwrap()
+        };
+        assert_eq!(call("p", "f"), response);
+        assert!(call("p", "g").is_empty());
+        assert!(call("q", "f").is_empty());
+
+        assert!(stub.lock_acquire("a"));
+        assert!(!stub.lock_acquire("a"));
+        assert!(stub.lock_release("a"));
+        assert!(!stub.lock_release("a"));
+
+        stub.artifact_write("a", b"1");
+        stub.artifact_write("a", b"2");
+        assert_eq!(stub.artifacts.read().unwrap()["a"], b"2");
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use actix_cloud::{
+    tokio::{runtime, task::JoinHandle, time},
+    tracing::{error, info},
+};
+use serde::{Deserialize, Serialize};
+use skynet_api::{
+    Result, bail,
+    sea_orm::{ConnectionTrait, TransactionTrait},
+};
+use skynet_api_task::{
+    entity::scripts::Concurrency,
+    viewer::{
+        scripts::{ScriptInfo, ScriptPatch, ScriptViewer},
+        settings::SettingViewer,
+        tags::TagViewer,
+    },
+};
+use validator::{Validate, ValidationError};
+
+use crate::{
+    PLUGIN_INSTANCE, Plugin,
+    api::{normalize_folder, validate_tags},
+};
+
+const SETTING_NAME: &str = "sync";
+const INTERVAL: Duration = Duration::from_secs(30);
+const EXTENSION: &str = "rhai";
+
+fn validate_path(path: &str) -> Result<(), ValidationError> {
+    if Path::new(path).is_absolute() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("absolute"))
+    }
+}
+
+/// Script sync settings, scripts are not synced if `path` is unset.
+#[derive(Debug, Default, Clone, Validate, Serialize, Deserialize)]
+pub struct SyncSetting {
+    /// Absolute path of the directory the scripts are synced from.
+    #[validate(length(min = 1, max = 1024), custom(function = "validate_path"))]
+    pub path: Option<String>,
+}
+
+impl SyncSetting {
+    pub async fn get<C>(db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(SettingViewer::get(db, SETTING_NAME)
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn set<C>(&self, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        SettingViewer::set(db, SETTING_NAME, self).await
+    }
+}
+
+/// Metadata in the leading `//! key: value` lines of a script file.
+#[derive(Debug, Default)]
+struct FrontMatter {
+    name: Option<String>,
+    library: bool,
+    concurrency: Concurrency,
+    description: Option<String>,
+    folder: Option<String>,
+    tags: Vec<String>,
+}
+
+impl FrontMatter {
+    fn parse(code: &str) -> Result<Self> {
+        let mut ret = Self::default();
+        for line in code.lines() {
+            let Some(line) = line.trim().strip_prefix("//!") else {
+                break;
+            };
+            let Some((k, v)) = line.split_once(':') else {
+                continue;
+            };
+            let v = v.trim();
+            match k.trim() {
+                "name" => ret.name = Some(v.to_owned()),
+                "library" => ret.library = v.parse()?,
+                "concurrency" => {
+                    ret.concurrency = serde_json::from_value(serde_json::Value::from(v))?
+                }
+                "description" => ret.description = Some(v.to_owned()),
+                "folder" => ret.folder = Some(v.to_owned()),
+                "tags" => {
+                    ret.tags = v
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|x| !x.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect()
+                }
+                k => bail!("Unknown front-matter `{k}`"),
+            }
+        }
+        ret.tags.sort();
+        ret.tags.dedup();
+        Ok(ret)
+    }
+}
+
+/// Directory the scripts are synced from, `None` if not configured.
+pub async fn root<C>(db: &C) -> Result<Option<PathBuf>>
+where
+    C: ConnectionTrait,
+{
+    Ok(SyncSetting::get(db).await?.path.map(PathBuf::from))
+}
+
+/// Check `info` and `tags` against the limits of the script API.
+fn validate(info: &ScriptInfo, tags: &[String]) -> Result<()> {
+    if info.name.is_empty() || info.name.chars().count() > 128 {
+        bail!("Invalid name length");
+    }
+    if info
+        .description
+        .as_ref()
+        .is_some_and(|x| x.chars().count() > 1024)
+    {
+        bail!("Invalid description length");
+    }
+    if info
+        .folder
+        .as_ref()
+        .is_some_and(|x| x.chars().count() > 256)
+    {
+        bail!("Invalid folder length");
+    }
+    if tags.len() > 32 || validate_tags(tags).is_err() {
+        bail!("Invalid tags");
+    }
+    Ok(())
+}
+
+/// Find `*.rhai` files in `dir` recursively, symlinks are skipped.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for x in fs::read_dir(dir)? {
+        let x = x?;
+        let ty = x.file_type()?;
+        let path = x.path();
+        if ty.is_symlink() {
+            continue;
+        }
+        if ty.is_dir() {
+            walk(&path, files)?;
+        } else if path.extension().is_some_and(|x| x == EXTENSION) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Upsert scripts from files, delete synced scripts whose file is removed.
+/// Return `(changed, deleted)` scripts.
+///
+/// Each file is synced in its own transaction, so that a failed file does not abort the others.
+pub async fn sync() -> Result<(u64, u64)> {
+    let db = PLUGIN_INSTANCE.db.get().unwrap();
+    let Some(root) = root(db).await? else {
+        return Ok((0, 0));
+    };
+    if !root.is_dir() {
+        return Ok((0, 0));
+    }
+    let mut files = Vec::new();
+    walk(&root, &mut files)?;
+
+    let old = ScriptViewer::find_synced(db).await?;
+    let mut seen = BTreeSet::new();
+    let mut changed = 0;
+    for path in files {
+        let source = path
+            .strip_prefix(&root)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let ret = async {
+            let code = fs::read_to_string(&path)?;
+            let meta = FrontMatter::parse(&code)?;
+            if let Err(e) = Plugin::compile_script(&code) {
+                bail!("{e}");
+            }
+            let info = ScriptInfo {
+                name: meta.name.unwrap_or_else(|| {
+                    path.file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string()
+                }),
+                code,
+                library: meta.library,
+                concurrency: meta.concurrency,
+                description: meta.description,
+                // Defaults to the directory of the file.
+                folder: match meta.folder {
+                    Some(x) => normalize_folder(&x),
+                    None => source.rsplit_once('/').and_then(|x| normalize_folder(x.0)),
+                },
+                source: Some(source.clone()),
+            };
+            validate(&info, &meta.tags)?;
+            let tx = db.begin().await?;
+            let ret = match old.iter().find(|x| x.source == info.source) {
+                Some(x) => {
+                    let tags = TagViewer::get(&tx, &x.id).await?;
+                    if x.name != info.name
+                        || x.code != info.code
+                        || x.library != info.library
+                        || x.concurrency != info.concurrency
+                        || x.description != info.description
+                        || x.folder != info.folder
+                        || tags != meta.tags
+                    {
+                        if info.library
+                            && ScriptViewer::find_library(&tx, &info.name)
+                                .await?
+                                .is_some_and(|y| y.id != x.id)
+                        {
+                            bail!("Library script `{}` already exists", info.name);
+                        }
+                        ScriptViewer::update(
+                            &tx,
+                            &x.id,
+                            &ScriptPatch {
+                                name: Some(info.name),
+                                code: Some(info.code),
+                                library: Some(info.library),
+                                concurrency: Some(info.concurrency),
+                                description: Some(info.description),
+                                folder: Some(info.folder),
+                                ..Default::default()
+                            },
+                        )
+                        .await?;
+                        TagViewer::set(&tx, &x.id, &meta.tags).await?;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                None => {
+                    if ScriptViewer::find_by_name(&tx, &info.name).await?.is_some() {
+                        bail!("Script `{}` already exists", info.name);
+                    }
+                    let m = ScriptViewer::create(&tx, &info).await?;
+                    TagViewer::set(&tx, &m.id, &meta.tags).await?;
+                    true
+                }
+            };
+            tx.commit().await?;
+            Ok::<_, skynet_api::anyhow::Error>(ret)
+        }
+        .await;
+        match ret {
+            Ok(x) => changed += u64::from(x),
+            Err(e) => error!(success = false, source, error = %e, "Sync script"),
+        }
+        seen.insert(source);
+    }
+    let id: Vec<_> = old
+        .into_iter()
+        .filter(|x| x.source.as_ref().is_some_and(|x| !seen.contains(x)))
+        .map(|x| x.id)
+        .collect();
+    let deleted = if id.is_empty() {
+        0
+    } else {
+        let tx = db.begin().await?;
+        let ret = ScriptViewer::delete(&tx, &id).await?;
+        tx.commit().await?;
+        ret
+    };
+    Ok((changed, deleted))
+}
+
+/// Start the background job syncing scripts from [`root`] if configured.
+pub fn start() -> JoinHandle<()> {
+    runtime::Handle::current().spawn(async {
+        loop {
+            match sync().await {
+                Ok((0, 0)) => {}
+                Ok((changed, deleted)) => {
+                    info!(success = true, changed, deleted, "Sync scripts")
+                }
+                Err(e) => error!(success = false, error = %e, "Sync scripts"),
+            }
+            time::sleep(INTERVAL).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_front_matter() {
+        let code = [
+            "//! name: backup",
+            "//! library: true",
+            "//! concurrency: skip",
+            "//! description: Daily backup: full",
+            "//! folder: ops/db",
+            "//! tags: b, a, ,b",
+            "//! no separator",
+            "let x = 1;",
+            "//! name: ignored after code",
+        ]
+        .join("\n");
+        let meta = FrontMatter::parse(&code).unwrap();
+        assert_eq!(meta.name.as_deref(), Some("backup"));
+        assert!(meta.library);
+        assert_eq!(meta.concurrency, Concurrency::Skip);
+        assert_eq!(meta.description.as_deref(), Some("Daily backup: full"));
+        assert_eq!(meta.folder.as_deref(), Some("ops/db"));
+        assert_eq!(meta.tags, ["a", "b"]);
+    }
+
+    #[test]
+    fn parse_front_matter_default() {
+        let meta = FrontMatter::parse("let x = 1;\n//! name: ignored").unwrap();
+        assert!(meta.name.is_none());
+        assert!(!meta.library);
+        assert_eq!(meta.concurrency, Concurrency::Allow);
+        assert!(meta.tags.is_empty());
+    }
+
+    #[test]
+    fn validate_limits() {
+        let info = ScriptInfo {
+            name: String::from("backup"),
+            ..Default::default()
+        };
+        assert!(validate(&info, &[]).is_ok());
+        assert!(
+            validate(
+                &ScriptInfo {
+                    name: "a".repeat(129),
+                    ..Default::default()
+                },
+                &[]
+            )
+            .is_err()
+        );
+        assert!(
+            validate(
+                &ScriptInfo {
+                    description: Some("a".repeat(1025)),
+                    ..info.clone()
+                },
+                &[]
+            )
+            .is_err()
+        );
+        assert!(
+            validate(
+                &ScriptInfo {
+                    folder: Some("a".repeat(257)),
+                    ..info.clone()
+                },
+                &[]
+            )
+            .is_err()
+        );
+        assert!(validate(&info, &["a".repeat(65)]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_skip_symlink() {
+        let dir = std::env::temp_dir().join(format!("task-sync-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/a.rhai"), "").unwrap();
+        fs::write(dir.join("b.txt"), "").unwrap();
+        std::os::unix::fs::symlink(dir.join("sub"), dir.join("link")).unwrap();
+        std::os::unix::fs::symlink(dir.join("sub/a.rhai"), dir.join("c.rhai")).unwrap();
+        let mut files = Vec::new();
+        let ret = walk(&dir, &mut files);
+        fs::remove_dir_all(&dir).unwrap();
+        ret.unwrap();
+        assert_eq!(files, [dir.join("sub/a.rhai")]);
+    }
+
+    #[test]
+    fn parse_front_matter_invalid() {
+        assert!(FrontMatter::parse("//! unknown: x").is_err());
+        assert!(FrontMatter::parse("//! library: yes").is_err());
+        assert!(FrontMatter::parse("//! concurrency: never").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use skynet_api::{
+    HyUuid, Result,
+    entity::permissions,
+    permission::{PERM_READ, PermChecker},
+    request::Request,
+    sea_orm::{
+        ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect,
+        prelude::Uuid,
+    },
+};
+use skynet_api_task::{entity::tasks, viewer::settings::SettingViewer};
+use validator::Validate;
+
+use crate::PLUGIN_INSTANCE;
+
+const SETTING_NAME: &str = "visibility";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Task viewers see all tasks.
+    #[default]
+    All,
+    /// Users see their own tasks and tasks of plugins they have any permission of.
+    Owner,
+}
+
+/// Visibility of tasks, users with `admin.{ID}` always see all tasks.
+#[derive(Debug, Default, Clone, Validate, Serialize, Deserialize)]
+pub struct Visibility {
+    pub mode: Mode,
+}
+
+impl Visibility {
+    pub async fn get<C>(db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(SettingViewer::get(db, SETTING_NAME)
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn set<C>(&self, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        SettingViewer::set(db, SETTING_NAME, self).await
+    }
+
+    /// Plugins `req` has any permission of, found by the `{name}.{plugin}` naming.
+    async fn plugins<C>(db: &C, req: &Request) -> Result<Vec<HyUuid>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(permissions::Entity::find()
+            .filter(permissions::Column::Id.is_in(req.perm.keys().copied()))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|x| {
+                x.name
+                    .rsplit_once('.')
+                    .and_then(|x| x.1.parse::<Uuid>().ok())
+                    .map(HyUuid)
+            })
+            .collect())
+    }
+
+    /// Condition of tasks visible to `req`, `None` if all tasks are visible.
+    ///
+    /// Tasks without owner, e.g. created by unknown plugins, are only visible to admins.
+    pub async fn cond<C>(db: &C, req: &Request) -> Result<Option<Condition>>
+    where
+        C: ConnectionTrait,
+    {
+        if Self::get(db).await?.mode == Mode::All
+            || PermChecker::new_entry(*PLUGIN_INSTANCE.admin_id.get().unwrap(), PERM_READ)
+                .check(&req.perm)
+        {
+            return Ok(None);
+        }
+        let mut cond =
+            Condition::any().add(tasks::Column::Plugin.is_in(Self::plugins(db, req).await?));
+        if let Some(uid) = req.uid {
+            cond = cond.add(tasks::Column::Uid.eq(uid));
+        }
+        Ok(Some(cond))
+    }
+
+    /// Whether task `id` is visible to `req`.
+    pub async fn visible<C>(db: &C, req: &Request, id: &HyUuid) -> Result<bool>
+    where
+        C: ConnectionTrait,
+    {
+        match Self::cond(db, req).await? {
+            Some(cond) => Ok(tasks::Entity::find_by_id(*id)
+                .filter(cond)
+                .one(db)
+                .await?
+                .is_some()),
+            None => Ok(true),
+        }
+    }
+
+    /// Tasks in `id` visible to `req`.
+    pub async fn filter<C>(db: &C, req: &Request, id: &[HyUuid]) -> Result<Vec<HyUuid>>
+    where
+        C: ConnectionTrait,
+    {
+        match Self::cond(db, req).await? {
+            Some(cond) => Ok(tasks::Entity::find()
+                .select_only()
+                .column(tasks::Column::Id)
+                .filter(tasks::Column::Id.is_in(id.iter().copied()))
+                .filter(cond)
+                .into_tuple()
+                .all(db)
+                .await?),
+            None => Ok(id.to_vec()),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use actix_cloud::chrono;
+use actix_cloud::macros::{entity_behavior, entity_id, entity_timestamp};
+use serde::{Deserialize, Serialize};
+use skynet_api::sea_orm::{self, prelude::*};
+
+use crate::HyUuid;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Default, Serialize, Deserialize)]
+#[sea_orm(table_name = "4adaf7d3-b877-43c3-82bd-da3689dc3920_acls")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: HyUuid,
+    pub sid: HyUuid,
+    /// User or group granted `perm`.
+    pub principal: HyUuid,
+    /// Whether `principal` is a group.
+    pub group: bool,
+    /// Bitmask of `ACL_*` rights.
+    pub perm: i32,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::scripts::Entity",
+        from = "Column::Sid",
+        to = "super::scripts::Column::Id"
+    )]
+    Script,
+}
+
+impl Related<super::scripts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Script.def()
+    }
+}
+
+#[entity_id(HyUuid::new())]
+#[entity_timestamp]
+impl ActiveModel {}
+
+#[entity_behavior]
+impl ActiveModelBehavior for ActiveModel {}
@@ -5,6 +5,28 @@ use skynet_api::sea_orm::{self, prelude::*};
 
 use crate::HyUuid;
 
+/// Policy applied when a script is run while its previous tasks are still running.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Default, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+#[serde(rename_all = "lowercase")]
+pub enum Concurrency {
+    /// Run concurrently.
+    #[default]
+    #[sea_orm(num_value = 0)]
+    Allow,
+    /// Do not run, return the running task instead.
+    #[sea_orm(num_value = 1)]
+    Skip,
+    /// Wait until previous tasks finish.
+    #[sea_orm(num_value = 2)]
+    Queue,
+    /// Stop previous tasks and run.
+    #[sea_orm(num_value = 3)]
+    Cancel,
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Default, Serialize, Deserialize)]
 #[sea_orm(table_name = "4adaf7d3-b877-43c3-82bd-da3689dc3920_scripts")]
 pub struct Model {
@@ -12,6 +34,23 @@ pub struct Model {
     pub id: HyUuid,
     pub name: String,
     pub code: String,
+    pub library: bool,
+    pub concurrency: Concurrency,
+    /// File the script is synced from, synced scripts are read-only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Folder path separated by `/`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// Disabled scripts cannot be run.
+    pub enabled: bool,
+    /// Archived scripts are hidden and cannot be run, their tasks are kept.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<i64>,
+    /// Runs of the script wait for approval of another user.
+    pub approval: bool,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -20,6 +59,14 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::tasks::Entity")]
     Task,
+    #[sea_orm(has_many = "super::states::Entity")]
+    State,
+    #[sea_orm(has_many = "super::notifications::Entity")]
+    Notification,
+    #[sea_orm(has_many = "super::tags::Entity")]
+    Tag,
+    #[sea_orm(has_many = "super::acls::Entity")]
+    Acl,
 }
 
 impl Related<super::tasks::Entity> for Entity {
@@ -28,6 +75,37 @@ impl Related<super::tasks::Entity> for Entity {
     }
 }
 
+impl Related<super::states::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::State.def()
+    }
+}
+
+impl Related<super::notifications::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Notification.def()
+    }
+}
+
+impl Related<super::tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Tag.def()
+    }
+}
+
+impl Related<super::acls::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Acl.def()
+    }
+}
+
+impl Model {
+    /// Whether the script can be run.
+    pub fn runnable(&self) -> bool {
+        self.enabled && self.archived_at.is_none()
+    }
+}
+
 #[entity_id(HyUuid::new())]
 #[entity_timestamp]
 impl ActiveModel {}
@@ -5,6 +5,20 @@ use skynet_api::sea_orm::{self, prelude::*};
 
 use crate::HyUuid;
 
+/// Approval state of a task run by a script requiring approval.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+#[serde(rename_all = "lowercase")]
+pub enum Approval {
+    /// Awaiting approval, not started yet.
+    #[sea_orm(num_value = 0)]
+    Pending,
+    #[sea_orm(num_value = 1)]
+    Approved,
+    #[sea_orm(num_value = 2)]
+    Rejected,
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Default, Serialize, Deserialize)]
 #[sea_orm(table_name = "4adaf7d3-b877-43c3-82bd-da3689dc3920_tasks")]
 pub struct Model {
@@ -19,6 +33,36 @@ pub struct Model {
     pub result: Option<i32>,
     pub sid: Option<HyUuid>,
     pub percent: i32,
+    pub dry_run: bool,
+    #[serde(skip)]
+    pub code: Option<String>,
+    #[serde(skip)]
+    pub params: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<HyUuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<HyUuid>,
+    /// Plugin owning the task, `None` for tasks run by users or of unknown plugins.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<HyUuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// Name of the script when the task was created, kept after the script is deleted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval: Option<Approval>,
+    /// User who approved or rejected the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approver: Option<HyUuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decided_at: Option<i64>,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -28,9 +72,12 @@ pub enum Relation {
     #[sea_orm(
         belongs_to = "super::scripts::Entity",
         from = "Column::Sid",
-        to = "super::scripts::Column::Id"
+        to = "super::scripts::Column::Id",
+        on_delete = "SetNull"
     )]
     Script,
+    #[sea_orm(has_many = "super::artifacts::Entity")]
+    Artifact,
 }
 
 impl Related<super::scripts::Entity> for Entity {
@@ -39,6 +86,12 @@ impl Related<super::scripts::Entity> for Entity {
     }
 }
 
+impl Related<super::artifacts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Artifact.def()
+    }
+}
+
 #[entity_id(HyUuid::new())]
 #[entity_timestamp]
 impl ActiveModel {}
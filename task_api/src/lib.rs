@@ -21,18 +21,117 @@ pub const ID: HyUuid = HyUuid(uuid!("4adaf7d3-b877-43c3-82bd-da3689dc3920"));
 pub trait Service: Send + Sync {
     async fn api_version() -> Version;
     async fn create(name: String, detail: Option<String>, cb: String) -> SResult<HyUuid>;
+    /// Same as `create`, but return the task created with idempotency `key` by the same
+    /// plugin within the last 24 hours instead of creating a new one. `cb` must be the plugin ID.
+    async fn create_with_key(
+        name: String,
+        detail: Option<String>,
+        cb: String,
+        key: String,
+    ) -> SResult<HyUuid>;
     async fn stop(id: HyUuid) -> bool;
+    /// Run script `sid` in a new task. The task has no owner and is only visible to admins
+    /// when tasks are scoped by owner, use `create_script_by` to record the calling plugin.
     async fn create_script(
         name: String,
         detail: Option<String>,
         sid: HyUuid,
     ) -> SResult<Option<HyUuid>>;
+    /// Same as `create_script`, the task is owned by `plugin` so that users with any permission
+    /// of `plugin` can see it.
+    async fn create_script_by(
+        name: String,
+        detail: Option<String>,
+        sid: HyUuid,
+        plugin: HyUuid,
+    ) -> SResult<Option<HyUuid>>;
+    /// Same as `create_script`, the task is owned by `plugin` and deduplicated by `key`.
+    async fn create_script_with_key(
+        name: String,
+        detail: Option<String>,
+        sid: HyUuid,
+        plugin: HyUuid,
+        key: String,
+    ) -> SResult<Option<HyUuid>>;
+    /// Run `code` in a new task. The task has no owner and is only visible to admins when
+    /// tasks are scoped by owner, use `create_code_by` to record the calling plugin.
     async fn create_code(name: String, detail: Option<String>, code: String) -> SResult<HyUuid>;
+    /// Same as `create_code`, the task is owned by `plugin`.
+    async fn create_code_by(
+        name: String,
+        detail: Option<String>,
+        code: String,
+        plugin: HyUuid,
+    ) -> SResult<HyUuid>;
+    /// Same as `create_code`, the task is owned by `plugin` and deduplicated by `key`.
+    async fn create_code_with_key(
+        name: String,
+        detail: Option<String>,
+        code: String,
+        plugin: HyUuid,
+        key: String,
+    ) -> SResult<HyUuid>;
+    async fn attach(id: HyUuid, name: String, content: Vec<u8>) -> SResult<Option<HyUuid>>;
+    async fn subscribe(cb: String, filter: EventFilter);
+    async fn unsubscribe(cb: String) -> bool;
+    /// Append `output` and add `percent` to plugin-managed task `id`, emitting a progress event.
+    /// Returns `false` if the task is not found.
+    async fn update(id: HyUuid, output: String, percent: u32) -> SResult<bool>;
+    /// Finish plugin-managed task `id` with `result` and fire its events and notifications.
+    /// Returns `false` if the task is not found or already finished.
+    async fn finish(id: HyUuid, result: i32) -> SResult<bool>;
 }
 
 #[plugin_api(TaskCallback)]
 pub trait Callback: Send + Sync {
     async fn stop(id: HyUuid) -> bool;
+    /// Called by notification rules when task `id` finishes with `result`, does nothing by default.
+    async fn on_finish(id: HyUuid, result: i32) {
+        let _ = (id, result);
+    }
+}
+
+#[plugin_api(TaskEvents)]
+pub trait Events: Send + Sync {
+    async fn on_event(event: TaskEvent);
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskEventKind {
+    Created,
+    Progress,
+    Finished,
+    Stopped,
+}
+
+/// Task lifecycle event delivered to [`TaskEvents`] subscribers.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TaskEvent {
+    pub kind: TaskEventKind,
+    pub id: HyUuid,
+    pub sid: Option<HyUuid>,
+    pub name: String,
+    pub percent: i32,
+    pub result: Option<i32>,
+}
+
+/// Filter of task events, unset fields match all events.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Event kinds to receive, all kinds if empty.
+    pub kinds: Vec<TaskEventKind>,
+    /// Only receive events of tasks run by script `sid`.
+    pub sid: Option<HyUuid>,
+    /// Only receive events of task `id`.
+    pub id: Option<HyUuid>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &TaskEvent) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&event.kind))
+            && self.sid.is_none_or(|x| event.sid == Some(x))
+            && self.id.is_none_or(|x| event.id == x)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, EnumAsInner)]
@@ -78,3 +177,62 @@ pub trait Script: Send + Sync {
     async fn call(name: String, param: BTreeMap<String, Value>)
     -> SResult<BTreeMap<String, Value>>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: TaskEventKind, sid: Option<HyUuid>) -> TaskEvent {
+        TaskEvent {
+            kind,
+            id: HyUuid::new(),
+            sid,
+            name: String::from("test"),
+            percent: 0,
+            result: None,
+        }
+    }
+
+    #[test]
+    fn event_filter_matches() {
+        let sid = HyUuid::new();
+        let e = event(TaskEventKind::Finished, Some(sid));
+        assert!(EventFilter::default().matches(&e));
+        assert!(
+            EventFilter {
+                kinds: vec![TaskEventKind::Created, TaskEventKind::Finished],
+                sid: Some(sid),
+                id: Some(e.id),
+            }
+            .matches(&e)
+        );
+        assert!(
+            !EventFilter {
+                kinds: vec![TaskEventKind::Progress],
+                ..Default::default()
+            }
+            .matches(&e)
+        );
+        assert!(
+            !EventFilter {
+                sid: Some(HyUuid::new()),
+                ..Default::default()
+            }
+            .matches(&e)
+        );
+        assert!(
+            !EventFilter {
+                sid: Some(sid),
+                ..Default::default()
+            }
+            .matches(&event(TaskEventKind::Finished, None))
+        );
+        assert!(
+            !EventFilter {
+                id: Some(HyUuid::new()),
+                ..Default::default()
+            }
+            .matches(&e)
+        );
+    }
+}
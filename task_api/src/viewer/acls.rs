@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use skynet_api::{
+    HyUuid, Result,
+    sea_orm::{
+        ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect, Set,
+    },
+};
+
+use crate::entity::acls;
+
+/// Run the script.
+pub const ACL_RUN: i32 = 1;
+/// View the code of the script.
+pub const ACL_VIEW: i32 = 2;
+/// Edit the script.
+pub const ACL_EDIT: i32 = 4;
+
+/// Rights of a user or group on a script.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AclEntry {
+    pub principal: HyUuid,
+    /// Whether `principal` is a group, granting `perm` to all its members.
+    #[serde(default)]
+    pub group: bool,
+    pub perm: i32,
+}
+
+impl From<acls::Model> for AclEntry {
+    fn from(x: acls::Model) -> Self {
+        Self {
+            principal: x.principal,
+            group: x.group,
+            perm: x.perm,
+        }
+    }
+}
+
+impl AclEntry {
+    /// Whether the entry applies to user `uid` in groups `gid`.
+    pub fn matches(&self, uid: &HyUuid, gid: &[HyUuid]) -> bool {
+        if self.group {
+            gid.contains(&self.principal)
+        } else {
+            self.principal == *uid
+        }
+    }
+}
+
+pub struct AclViewer;
+
+impl AclViewer {
+    /// Get ACL of script `sid`, empty if the script is not restricted.
+    pub async fn get<C>(db: &C, sid: &HyUuid) -> Result<Vec<AclEntry>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(acls::Entity::find()
+            .filter(acls::Column::Sid.eq(*sid))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Replace ACL of script `sid` with `acl`.
+    pub async fn set<C>(db: &C, sid: &HyUuid, acl: &[AclEntry]) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        acls::Entity::delete_many()
+            .filter(acls::Column::Sid.eq(*sid))
+            .exec(db)
+            .await?;
+        for x in acl {
+            acls::ActiveModel {
+                sid: Set(*sid),
+                principal: Set(x.principal),
+                group: Set(x.group),
+                perm: Set(x.perm),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Find scripts restricted by ACL.
+    pub async fn find_restricted<C>(db: &C) -> Result<Vec<HyUuid>>
+    where
+        C: ConnectionTrait,
+    {
+        acls::Entity::find()
+            .select_only()
+            .column(acls::Column::Sid)
+            .distinct()
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Find scripts on which user `uid` or any of its groups `gid` is granted `perm`.
+    pub async fn find_allowed<C>(
+        db: &C,
+        uid: &HyUuid,
+        gid: &[HyUuid],
+        perm: i32,
+    ) -> Result<Vec<HyUuid>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(acls::Entity::find()
+            .filter(acls::Column::Principal.is_in(gid.iter().chain([uid]).copied()))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|x| {
+                let sid = x.sid;
+                let x = AclEntry::from(x);
+                (x.perm & perm != 0 && x.matches(uid, gid)).then_some(sid)
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect())
+    }
+
+    /// Whether user `uid` or any of its groups `gid` is granted `perm` on script `sid`.
+    /// Scripts without ACL are allowed, anonymous users are denied on restricted scripts.
+    pub async fn check<C>(
+        db: &C,
+        sid: &HyUuid,
+        uid: Option<HyUuid>,
+        gid: &[HyUuid],
+        perm: i32,
+    ) -> Result<bool>
+    where
+        C: ConnectionTrait,
+    {
+        let acl = Self::get(db, sid).await?;
+        Ok(acl.is_empty()
+            || uid.is_some_and(|uid| {
+                acl.iter()
+                    .any(|x| x.perm & perm != 0 && x.matches(&uid, gid))
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_matches() {
+        let (uid, gid) = (HyUuid::new(), HyUuid::new());
+        let user = AclEntry {
+            principal: uid,
+            group: false,
+            perm: ACL_RUN,
+        };
+        let group = AclEntry {
+            principal: gid,
+            group: true,
+            perm: ACL_RUN,
+        };
+        assert!(user.matches(&uid, &[]));
+        assert!(!user.matches(&gid, &[uid]));
+        assert!(group.matches(&uid, &[gid]));
+        assert!(!group.matches(&gid, &[]));
+    }
+}
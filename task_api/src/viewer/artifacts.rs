@@ -0,0 +1,46 @@
+use skynet_api::{
+    HyUuid, Result, anyhow,
+    hyuuid::uuids2strings,
+    request::Condition,
+    sea_orm::{
+        self, ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait,
+        QueryFilter, QueryOrder, Set,
+    },
+};
+use skynet_macro::default_viewer;
+
+use crate::entity::artifacts;
+
+pub struct ArtifactViewer;
+
+#[default_viewer(artifacts)]
+impl ArtifactViewer {
+    /// Create artifact `name` of task `tid` with `size` bytes.
+    pub async fn create<C>(db: &C, tid: &HyUuid, name: &str, size: i64) -> Result<artifacts::Model>
+    where
+        C: ConnectionTrait,
+    {
+        artifacts::ActiveModel {
+            tid: Set(*tid),
+            name: Set(name.to_owned()),
+            size: Set(size),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Find all artifacts of task `tid`.
+    pub async fn find_by_tid<C>(db: &C, tid: &HyUuid) -> Result<Vec<artifacts::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        artifacts::Entity::find()
+            .filter(artifacts::Column::Tid.eq(*tid))
+            .order_by_asc(artifacts::Column::CreatedAt)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+}
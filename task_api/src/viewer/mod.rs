@@ -1,2 +1,9 @@
+pub mod acls;
+pub mod artifacts;
+pub mod notifications;
 pub mod scripts;
+pub mod secrets;
+pub mod settings;
+pub mod states;
+pub mod tags;
 pub mod tasks;
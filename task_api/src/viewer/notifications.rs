@@ -0,0 +1,98 @@
+use skynet_api::{
+    HyUuid, Result, anyhow,
+    hyuuid::uuids2strings,
+    request::Condition,
+    sea_orm::{
+        self, ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait,
+        QueryFilter, Set, Unchanged,
+    },
+};
+use skynet_macro::default_viewer;
+
+use crate::entity::notifications;
+
+/// Notification rule fired when a task finishes.
+#[derive(Debug, Default, Clone)]
+pub struct NotificationRule {
+    pub name: String,
+    /// Webhook receiving the task as JSON.
+    pub url: Option<String>,
+    /// Registry name of the `TaskCallback` to notify.
+    pub callback: Option<String>,
+    /// Only notify tasks of script `sid`, all tasks if not set.
+    pub sid: Option<HyUuid>,
+    pub on_success: bool,
+    pub on_failure: bool,
+}
+
+pub struct NotificationViewer;
+
+#[default_viewer(notifications)]
+impl NotificationViewer {
+    pub async fn create<C>(db: &C, rule: &NotificationRule) -> Result<notifications::Model>
+    where
+        C: ConnectionTrait,
+    {
+        notifications::ActiveModel {
+            name: Set(rule.name.clone()),
+            url: Set(rule.url.clone()),
+            callback: Set(rule.callback.clone()),
+            sid: Set(rule.sid),
+            on_success: Set(rule.on_success),
+            on_failure: Set(rule.on_failure),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn update<C>(
+        db: &C,
+        id: &HyUuid,
+        rule: &NotificationRule,
+    ) -> Result<notifications::Model>
+    where
+        C: ConnectionTrait,
+    {
+        notifications::ActiveModel {
+            id: Unchanged(*id),
+            name: Set(rule.name.clone()),
+            url: Set(rule.url.clone()),
+            callback: Set(rule.callback.clone()),
+            sid: Set(rule.sid),
+            on_success: Set(rule.on_success),
+            on_failure: Set(rule.on_failure),
+            ..Default::default()
+        }
+        .update(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Find rules matching a finished task of script `sid`.
+    pub async fn find_match<C>(
+        db: &C,
+        sid: Option<HyUuid>,
+        success: bool,
+    ) -> Result<Vec<notifications::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        let mut script = sea_orm::Condition::any().add(notifications::Column::Sid.is_null());
+        if let Some(sid) = sid {
+            script = script.add(notifications::Column::Sid.eq(sid));
+        }
+        let status = if success {
+            notifications::Column::OnSuccess.eq(true)
+        } else {
+            notifications::Column::OnFailure.eq(true)
+        };
+        notifications::Entity::find()
+            .filter(script)
+            .filter(status)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+}
@@ -1,27 +1,64 @@
+use std::collections::HashMap;
+
 use skynet_api::{
     HyUuid, Result, anyhow,
     hyuuid::uuids2strings,
     request::Condition,
     sea_orm::{
         self, ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, ConnectionTrait, EntityTrait,
-        PaginatorTrait, QueryFilter, Set, Unchanged,
+        PaginatorTrait, QueryFilter, QuerySelect, Set, Unchanged,
     },
 };
 use skynet_macro::default_viewer;
 
-use crate::entity::scripts;
+use crate::entity::scripts::{self, Concurrency};
+
+/// Fields of a new script.
+#[derive(Debug, Default, Clone)]
+pub struct ScriptInfo {
+    pub name: String,
+    pub code: String,
+    /// Library scripts can only be imported by others.
+    pub library: bool,
+    pub concurrency: Concurrency,
+    pub description: Option<String>,
+    /// Folder path separated by `/`.
+    pub folder: Option<String>,
+    /// File the script is synced from.
+    pub source: Option<String>,
+}
+
+/// Changed fields of a script, `None` fields are unchanged.
+#[derive(Debug, Default, Clone)]
+pub struct ScriptPatch {
+    pub name: Option<String>,
+    pub code: Option<String>,
+    pub library: Option<bool>,
+    pub concurrency: Option<Concurrency>,
+    pub description: Option<Option<String>>,
+    pub folder: Option<Option<String>>,
+    pub enabled: Option<bool>,
+    pub archived_at: Option<Option<i64>>,
+    pub approval: Option<bool>,
+}
 
 pub struct ScriptViewer;
 
 #[default_viewer(scripts)]
 impl ScriptViewer {
-    pub async fn create<C>(db: &C, name: &str, code: &str) -> Result<scripts::Model>
+    pub async fn create<C>(db: &C, info: &ScriptInfo) -> Result<scripts::Model>
     where
         C: ConnectionTrait,
     {
         scripts::ActiveModel {
-            name: Set(name.to_owned()),
-            code: Set(code.to_owned()),
+            name: Set(info.name.clone()),
+            code: Set(info.code.clone()),
+            library: Set(info.library),
+            concurrency: Set(info.concurrency),
+            source: Set(info.source.clone()),
+            description: Set(info.description.clone()),
+            folder: Set(info.folder.clone()),
+            enabled: Set(true),
             ..Default::default()
         }
         .insert(db)
@@ -29,23 +66,105 @@ impl ScriptViewer {
         .map_err(Into::into)
     }
 
-    pub async fn update<C>(
-        db: &C,
-        id: &HyUuid,
-        name: Option<&str>,
-        code: Option<&str>,
-    ) -> Result<scripts::Model>
+    pub async fn update<C>(db: &C, id: &HyUuid, patch: &ScriptPatch) -> Result<scripts::Model>
     where
         C: ConnectionTrait,
     {
         scripts::ActiveModel {
             id: Unchanged(*id),
-            name: name.map_or(NotSet, |x| Set(x.to_owned())),
-            code: code.map_or(NotSet, |x| Set(x.to_owned())),
+            name: patch.name.clone().map_or(NotSet, Set),
+            code: patch.code.clone().map_or(NotSet, Set),
+            library: patch.library.map_or(NotSet, Set),
+            concurrency: patch.concurrency.map_or(NotSet, Set),
+            description: patch.description.clone().map_or(NotSet, Set),
+            folder: patch.folder.clone().map_or(NotSet, Set),
+            enabled: patch.enabled.map_or(NotSet, Set),
+            archived_at: patch.archived_at.map_or(NotSet, Set),
+            approval: patch.approval.map_or(NotSet, Set),
             ..Default::default()
         }
         .update(db)
         .await
         .map_err(Into::into)
     }
+
+    /// Find scripts in `id`.
+    pub async fn find_by_ids<C>(db: &C, id: &[HyUuid]) -> Result<Vec<scripts::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        scripts::Entity::find()
+            .filter(scripts::Column::Id.is_in(id.iter().copied()))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Find names of scripts in `id`.
+    pub async fn find_names<C>(db: &C, id: &[HyUuid]) -> Result<HashMap<HyUuid, String>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(scripts::Entity::find()
+            .select_only()
+            .columns([scripts::Column::Id, scripts::Column::Name])
+            .filter(scripts::Column::Id.is_in(id.iter().copied()))
+            .into_tuple()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect())
+    }
+
+    /// Find all scripts synced from files.
+    pub async fn find_synced<C>(db: &C) -> Result<Vec<scripts::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        scripts::Entity::find()
+            .filter(scripts::Column::Source.is_not_null())
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Find synced scripts in `id`.
+    pub async fn find_synced_in<C>(db: &C, id: &[HyUuid]) -> Result<Vec<HyUuid>>
+    where
+        C: ConnectionTrait,
+    {
+        scripts::Entity::find()
+            .select_only()
+            .column(scripts::Column::Id)
+            .filter(scripts::Column::Id.is_in(id.iter().copied()))
+            .filter(scripts::Column::Source.is_not_null())
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn find_by_name<C>(db: &C, name: &str) -> Result<Option<scripts::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        scripts::Entity::find()
+            .filter(scripts::Column::Name.eq(name))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Find library script `name`, library names are unique.
+    pub async fn find_library<C>(db: &C, name: &str) -> Result<Option<scripts::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        scripts::Entity::find()
+            .filter(scripts::Column::Name.eq(name))
+            .filter(scripts::Column::Library.eq(true))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
 }
@@ -0,0 +1,58 @@
+use skynet_api::{
+    HyUuid, Result, anyhow,
+    hyuuid::uuids2strings,
+    request::Condition,
+    sea_orm::{
+        self, ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait,
+        QueryFilter, Set, Unchanged,
+    },
+};
+use skynet_macro::default_viewer;
+
+use crate::entity::secrets;
+
+pub struct SecretViewer;
+
+#[default_viewer(secrets)]
+impl SecretViewer {
+    /// Create secret `name` with encrypted `value`.
+    pub async fn create<C>(db: &C, name: &str, value: &str) -> Result<secrets::Model>
+    where
+        C: ConnectionTrait,
+    {
+        secrets::ActiveModel {
+            name: Set(name.to_owned()),
+            value: Set(value.to_owned()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Update secret `id` with encrypted `value`.
+    pub async fn update<C>(db: &C, id: &HyUuid, value: &str) -> Result<secrets::Model>
+    where
+        C: ConnectionTrait,
+    {
+        secrets::ActiveModel {
+            id: Unchanged(*id),
+            value: Set(value.to_owned()),
+            ..Default::default()
+        }
+        .update(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn find_by_name<C>(db: &C, name: &str) -> Result<Option<secrets::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        secrets::Entity::find()
+            .filter(secrets::Column::Name.eq(name))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Serialize, de::DeserializeOwned};
+use skynet_api::{
+    Result,
+    sea_orm::{
+        ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter,
+        Set,
+    },
+};
+
+use crate::entity::settings;
+
+pub struct SettingViewer;
+
+impl SettingViewer {
+    async fn find_by_name<C>(db: &C, name: &str) -> Result<Option<settings::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        settings::Entity::find()
+            .filter(settings::Column::Name.eq(name))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Get setting `name`, `None` if not set.
+    pub async fn get<C, T>(db: &C, name: &str) -> Result<Option<T>>
+    where
+        C: ConnectionTrait,
+        T: DeserializeOwned,
+    {
+        match Self::find_by_name(db, name).await? {
+            Some(x) => Ok(Some(serde_json::from_str(&x.value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Set setting `name` to `value`.
+    pub async fn set<C, T>(db: &C, name: &str, value: &T) -> Result<()>
+    where
+        C: ConnectionTrait,
+        T: Serialize,
+    {
+        let value = serde_json::to_string(value)?;
+        match Self::find_by_name(db, name).await? {
+            Some(x) => {
+                let mut x = x.into_active_model();
+                x.value = Set(value);
+                x.update(db).await?;
+            }
+            None => {
+                settings::ActiveModel {
+                    name: Set(name.to_owned()),
+                    value: Set(value),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
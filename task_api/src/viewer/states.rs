@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+
+use skynet_api::{
+    HyUuid, Result,
+    sea_orm::{
+        ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter,
+        Set,
+    },
+};
+
+use crate::{Value, entity::states};
+
+pub struct StateViewer;
+
+impl StateViewer {
+    async fn find_key<C>(db: &C, sid: &HyUuid, key: &str) -> Result<Option<states::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        states::Entity::find()
+            .filter(states::Column::Sid.eq(*sid))
+            .filter(states::Column::Key.eq(key))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Get state `key` of script `sid`.
+    pub async fn get<C>(db: &C, sid: &HyUuid, key: &str) -> Result<Option<Value>>
+    where
+        C: ConnectionTrait,
+    {
+        match Self::find_key(db, sid, key).await? {
+            Some(x) => Ok(Some(serde_json::from_str(&x.value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get all states of script `sid`.
+    pub async fn get_all<C>(db: &C, sid: &HyUuid) -> Result<BTreeMap<String, Value>>
+    where
+        C: ConnectionTrait,
+    {
+        let mut ret = BTreeMap::new();
+        for x in states::Entity::find()
+            .filter(states::Column::Sid.eq(*sid))
+            .all(db)
+            .await?
+        {
+            ret.insert(x.key, serde_json::from_str(&x.value)?);
+        }
+        Ok(ret)
+    }
+
+    /// Set state `key` of script `sid` to `value`.
+    pub async fn set<C>(db: &C, sid: &HyUuid, key: &str, value: &Value) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let value = serde_json::to_string(value)?;
+        match Self::find_key(db, sid, key).await? {
+            Some(x) => {
+                let mut x = x.into_active_model();
+                x.value = Set(value);
+                x.update(db).await?;
+            }
+            None => {
+                states::ActiveModel {
+                    sid: Set(*sid),
+                    key: Set(key.to_owned()),
+                    value: Set(value),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete state `key` of script `sid`.
+    pub async fn delete<C>(db: &C, sid: &HyUuid, key: &str) -> Result<u64>
+    where
+        C: ConnectionTrait,
+    {
+        states::Entity::delete_many()
+            .filter(states::Column::Sid.eq(*sid))
+            .filter(states::Column::Key.eq(key))
+            .exec(db)
+            .await
+            .map(|x| x.rows_affected)
+            .map_err(Into::into)
+    }
+
+    /// Delete all states of script `sid`.
+    pub async fn reset<C>(db: &C, sid: &HyUuid) -> Result<u64>
+    where
+        C: ConnectionTrait,
+    {
+        states::Entity::delete_many()
+            .filter(states::Column::Sid.eq(*sid))
+            .exec(db)
+            .await
+            .map(|x| x.rows_affected)
+            .map_err(Into::into)
+    }
+}
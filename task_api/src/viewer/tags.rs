@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use skynet_api::{
+    HyUuid, Result,
+    sea_orm::{
+        ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+        QuerySelect, Set,
+    },
+};
+
+use crate::entity::tags;
+
+pub struct TagViewer;
+
+impl TagViewer {
+    /// Get tags of script `sid`.
+    pub async fn get<C>(db: &C, sid: &HyUuid) -> Result<Vec<String>>
+    where
+        C: ConnectionTrait,
+    {
+        tags::Entity::find()
+            .select_only()
+            .column(tags::Column::Name)
+            .filter(tags::Column::Sid.eq(*sid))
+            .order_by_asc(tags::Column::Name)
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Get tags of scripts in `sid`.
+    pub async fn get_map<C>(db: &C, sid: &[HyUuid]) -> Result<HashMap<HyUuid, Vec<String>>>
+    where
+        C: ConnectionTrait,
+    {
+        let mut ret: HashMap<HyUuid, Vec<String>> = HashMap::new();
+        for x in tags::Entity::find()
+            .filter(tags::Column::Sid.is_in(sid.iter().copied()))
+            .order_by_asc(tags::Column::Name)
+            .all(db)
+            .await?
+        {
+            ret.entry(x.sid).or_default().push(x.name);
+        }
+        Ok(ret)
+    }
+
+    /// Replace tags of script `sid` with `tags`.
+    pub async fn set<C>(db: &C, sid: &HyUuid, tags: &[String]) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        tags::Entity::delete_many()
+            .filter(tags::Column::Sid.eq(*sid))
+            .exec(db)
+            .await?;
+        let mut tags = tags.to_vec();
+        tags.sort();
+        tags.dedup();
+        for x in tags {
+            tags::ActiveModel {
+                sid: Set(*sid),
+                name: Set(x),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Find scripts with tag `name`.
+    pub async fn find_sids<C>(db: &C, name: &str) -> Result<Vec<HyUuid>>
+    where
+        C: ConnectionTrait,
+    {
+        tags::Entity::find()
+            .select_only()
+            .column(tags::Column::Sid)
+            .filter(tags::Column::Name.eq(name))
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+}
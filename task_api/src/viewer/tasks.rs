@@ -1,27 +1,67 @@
+use std::collections::BTreeMap;
+
+use actix_cloud::chrono::Utc;
 use skynet_api::{
     HyUuid, Result, anyhow,
     hyuuid::uuids2strings,
     request::Condition,
     sea_orm::{
         self, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseTransaction, EntityTrait,
-        IntoActiveModel, PaginatorTrait, QueryFilter, Set, Unchanged, prelude::Expr,
+        IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, Unchanged,
+        prelude::Expr,
     },
 };
 use skynet_macro::default_viewer;
 
-use crate::entity::tasks;
+use crate::{
+    Value,
+    entity::{
+        scripts,
+        tasks::{self, Approval},
+    },
+};
+
+/// Script run of a task.
+#[derive(Debug, Default, Clone)]
+pub struct TaskRun {
+    /// Script the code belongs to.
+    pub sid: Option<HyUuid>,
+    /// Code to run.
+    pub code: String,
+    /// Parameters passed to the script as `param`.
+    pub params: BTreeMap<String, Value>,
+    /// Task rerun by this task.
+    pub parent: Option<HyUuid>,
+    /// User who runs the script, `None` for plugins.
+    pub uid: Option<HyUuid>,
+    /// Dry-run tasks are only used to test scripts.
+    pub dry_run: bool,
+    /// Plugin which creates the task, `None` for users.
+    pub plugin: Option<HyUuid>,
+    /// Idempotency key of the creation request, unique within `plugin`.
+    pub key: Option<String>,
+}
 
 pub struct TaskViewer;
 
 #[default_viewer(tasks)]
 impl TaskViewer {
-    pub async fn create<C>(db: &C, name: &str, detail: &Option<String>) -> Result<tasks::Model>
+    pub async fn create<C>(
+        db: &C,
+        name: &str,
+        detail: &Option<String>,
+        key: &Option<String>,
+        plugin: Option<HyUuid>,
+    ) -> Result<tasks::Model>
     where
         C: ConnectionTrait,
     {
         tasks::ActiveModel {
             name: Set(name.to_owned()),
             detail: Set(detail.to_owned()),
+            idempotency_key: Set(key.to_owned()),
+            plugin: Set(plugin),
+            started_at: Set(Some(Utc::now().timestamp_millis())),
             ..Default::default()
         }
         .insert(db)
@@ -29,6 +69,135 @@ impl TaskViewer {
         .map_err(Into::into)
     }
 
+    /// Create task `name` running a script described by `run`.
+    /// `pending` tasks are not started until approved.
+    pub async fn create_run<C>(
+        db: &C,
+        name: &str,
+        detail: &Option<String>,
+        run: &TaskRun,
+        pending: bool,
+    ) -> Result<tasks::Model>
+    where
+        C: ConnectionTrait,
+    {
+        let script_name = match run.sid {
+            Some(sid) => scripts::Entity::find_by_id(sid)
+                .one(db)
+                .await?
+                .map(|x| x.name),
+            None => None,
+        };
+        tasks::ActiveModel {
+            name: Set(name.to_owned()),
+            detail: Set(detail.to_owned()),
+            sid: Set(run.sid),
+            script_name: Set(script_name),
+            code: Set(Some(run.code.clone())),
+            params: Set(Some(serde_json::to_string(&run.params)?)),
+            parent: Set(run.parent),
+            uid: Set(run.uid),
+            dry_run: Set(run.dry_run),
+            idempotency_key: Set(run.key.clone()),
+            plugin: Set(run.plugin),
+            approval: Set(pending.then_some(Approval::Pending)),
+            started_at: Set((!pending).then(|| Utc::now().timestamp_millis())),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Get the parameters of task `m`, `None` if it is not a script task.
+    pub fn params(m: &tasks::Model) -> Result<Option<BTreeMap<String, Value>>> {
+        match &m.params {
+            Some(x) => Ok(Some(serde_json::from_str(x)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Find task created by `plugin` with idempotency `key`.
+    pub async fn find_by_key<C>(
+        db: &C,
+        plugin: Option<HyUuid>,
+        key: &str,
+    ) -> Result<Option<tasks::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        let plugin = match plugin {
+            Some(x) => tasks::Column::Plugin.eq(x),
+            None => tasks::Column::Plugin.is_null(),
+        };
+        tasks::Entity::find()
+            .filter(plugin)
+            .filter(tasks::Column::IdempotencyKey.eq(key))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Clear the idempotency key of task `id` so that the key can be reused.
+    pub async fn clear_key<C>(db: &C, id: &HyUuid) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        tasks::ActiveModel {
+            id: Unchanged(*id),
+            idempotency_key: Set(None),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Record `approval` of pending task `id` by user `uid`, approved tasks are started.
+    /// Return `false` if the task is no longer pending, e.g. decided or stopped concurrently.
+    pub async fn decide<C>(
+        db: &C,
+        id: &HyUuid,
+        approval: Approval,
+        uid: Option<HyUuid>,
+        reason: &Option<String>,
+    ) -> Result<bool>
+    where
+        C: ConnectionTrait,
+    {
+        let now = Utc::now().timestamp_millis();
+        let mut q = tasks::Entity::update_many()
+            .col_expr(tasks::Column::Approval, Expr::value(approval))
+            .col_expr(tasks::Column::Approver, Expr::value(uid))
+            .col_expr(
+                tasks::Column::ApprovalReason,
+                Expr::value(reason.to_owned()),
+            )
+            .col_expr(tasks::Column::DecidedAt, Expr::value(now));
+        if approval == Approval::Approved {
+            q = q.col_expr(tasks::Column::StartedAt, Expr::value(now));
+        }
+        let rows = q
+            .filter(tasks::Column::Id.eq(*id))
+            .filter(tasks::Column::Approval.eq(Approval::Pending))
+            .filter(tasks::Column::Result.is_null())
+            .exec(db)
+            .await?
+            .rows_affected;
+        Ok(rows == 1)
+    }
+
+    /// Condition of running tasks, tasks awaiting approval are excluded.
+    fn running() -> sea_orm::Condition {
+        sea_orm::Condition::all()
+            .add(tasks::Column::Result.is_null())
+            .add(
+                sea_orm::Condition::any()
+                    .add(tasks::Column::Approval.is_null())
+                    .add(tasks::Column::Approval.ne(Approval::Pending)),
+            )
+    }
+
     /// Update task `id` with `output` and `percent`.
     pub async fn update(
         db: &DatabaseTransaction,
@@ -61,11 +230,15 @@ impl TaskViewer {
         let mut m = m.into_active_model();
         m.result = Set(Some(result));
         m.output = Set(Some(output));
+        m.finished_at = Set(Some(Utc::now().timestamp_millis()));
         m.update(db).await?;
         Ok(true)
     }
 
     /// Finish task `id` with `result`.
+    ///
+    /// This only writes the database, plugins should finish their tasks by `Service::finish`
+    /// so that notifications are fired.
     pub async fn finish<C>(db: &C, id: &HyUuid, result: i32) -> Result<()>
     where
         C: ConnectionTrait,
@@ -73,6 +246,7 @@ impl TaskViewer {
         tasks::ActiveModel {
             id: Unchanged(*id),
             result: Set(Some(result)),
+            finished_at: Set(Some(Utc::now().timestamp_millis())),
             ..Default::default()
         }
         .update(db)
@@ -93,14 +267,151 @@ impl TaskViewer {
             .map_err(Into::into)
     }
 
-    /// Clean all running tasks, mark result to unknown.
+    /// Delete completed tasks matching `cond`.
+    pub async fn delete_completed_by<C>(db: &C, cond: sea_orm::Condition) -> Result<u64>
+    where
+        C: ConnectionTrait,
+    {
+        tasks::Entity::delete_many()
+            .filter(tasks::Column::Result.is_not_null())
+            .filter(cond)
+            .exec(db)
+            .await
+            .map(|x| x.rows_affected)
+            .map_err(Into::into)
+    }
+
+    /// Find running tasks in `id`, tasks awaiting approval are excluded.
+    pub async fn find_running<C>(db: &C, id: &[HyUuid]) -> Result<Vec<HyUuid>>
+    where
+        C: ConnectionTrait,
+    {
+        tasks::Entity::find()
+            .select_only()
+            .column(tasks::Column::Id)
+            .filter(tasks::Column::Id.is_in(id.iter().copied()))
+            .filter(Self::running())
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Find running tasks of script `sid`, dry-run tasks are excluded.
+    pub async fn find_running_by_sid<C>(db: &C, sid: &HyUuid) -> Result<Vec<HyUuid>>
+    where
+        C: ConnectionTrait,
+    {
+        tasks::Entity::find()
+            .select_only()
+            .column(tasks::Column::Id)
+            .filter(tasks::Column::Sid.eq(*sid))
+            .filter(tasks::Column::DryRun.eq(false))
+            .filter(Self::running())
+            .order_by_asc(tasks::Column::CreatedAt)
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Find `(sid, result, started_at, finished_at)` of tasks matching `cond`.
+    #[allow(clippy::type_complexity)]
+    pub async fn find_stats<C>(
+        db: &C,
+        cond: sea_orm::Condition,
+    ) -> Result<Vec<(Option<HyUuid>, Option<i32>, Option<i64>, Option<i64>)>>
+    where
+        C: ConnectionTrait,
+    {
+        tasks::Entity::find()
+            .select_only()
+            .columns([
+                tasks::Column::Sid,
+                tasks::Column::Result,
+                tasks::Column::StartedAt,
+                tasks::Column::FinishedAt,
+            ])
+            .filter(cond)
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Count running tasks matching `cond`, dry-run tasks are excluded.
+    pub async fn count_running<C>(db: &C, cond: sea_orm::Condition) -> Result<u64>
+    where
+        C: ConnectionTrait,
+    {
+        tasks::Entity::find()
+            .filter(tasks::Column::DryRun.eq(false))
+            .filter(Self::running())
+            .filter(cond)
+            .count(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Count tasks of scripts in `sid`.
+    pub async fn count_by_sids<C>(db: &C, sid: &[HyUuid]) -> Result<u64>
+    where
+        C: ConnectionTrait,
+    {
+        tasks::Entity::find()
+            .filter(tasks::Column::Sid.is_in(sid.iter().copied()))
+            .count(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Find all scripts that have tasks.
+    pub async fn find_sids<C>(db: &C) -> Result<Vec<HyUuid>>
+    where
+        C: ConnectionTrait,
+    {
+        tasks::Entity::find()
+            .select_only()
+            .column(tasks::Column::Sid)
+            .distinct()
+            .filter(tasks::Column::Sid.is_not_null())
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Find completed tasks of script `sid` except the latest `keep` ones.
+    pub async fn find_completed_except<C>(
+        db: &C,
+        sid: &HyUuid,
+        keep: u64,
+    ) -> Result<Vec<tasks::Model>>
+    where
+        C: ConnectionTrait,
+    {
+        tasks::Entity::find()
+            .filter(tasks::Column::Sid.eq(*sid))
+            .filter(tasks::Column::Result.is_not_null())
+            .order_by_desc(tasks::Column::CreatedAt)
+            .offset(keep)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Clean all running tasks, mark result to unknown. Tasks awaiting approval are kept.
     pub async fn clean_running<C>(db: &C) -> Result<u64>
     where
         C: ConnectionTrait,
     {
         Ok(tasks::Entity::update_many()
             .col_expr(tasks::Column::Result, Expr::value(-1))
-            .filter(tasks::Column::Result.is_null())
+            .col_expr(
+                tasks::Column::FinishedAt,
+                Expr::value(Utc::now().timestamp_millis()),
+            )
+            .filter(Self::running())
             .exec(db)
             .await?
             .rows_affected)